@@ -0,0 +1,78 @@
+//! Random operations on an indexbag, shared by the example and the benchmarks.
+
+use rand::*;
+use rand::prng::XorShiftRng;
+
+use index_bag::{IndexBag, Index};
+
+#[derive(Clone, Copy)]
+pub enum Action {
+    Insert,
+    Remove,
+    Lookup,
+}
+pub use self::Action::*;
+pub const ALL_ACTIONS: &[Action] = &[Insert, Remove, Lookup];
+
+impl Action {
+    /// Perform the action on the bag, printing a line describing it if `log` is set.
+    pub fn enact(
+        &self,
+        rng: &mut impl Rng,
+        bag: &mut IndexBag<u16>,
+        values: &mut Vec<(u16, Index<u16>)>,
+        log: bool,
+    ) {
+        match self {
+            Insert => {
+                let value = rng.gen();
+                let index = bag.insert(value);
+                if log {
+                    eprintln!(
+                        "\x1B[1;32mINSERT\x1B[0m ({:4}/{:4}) {:04X}         @ {:?}",
+                        bag.unused_indexes(), bag.pool_size(), value, index
+                    );
+                }
+                values.push((value, index));
+            }
+            Remove => {
+                if let Some((value, index)) = values.pop() {
+                    let removed_value = bag.remove(index)
+                        .unwrap_or_else(|| panic!("Couldn't remove index {:?}", index));
+                    if log {
+                        eprintln!(
+                            "\x1B[1;31mREMOVE\x1b[0m ({:4}/{:4}) {:04X} == {:04X} @ {:?}",
+                            bag.unused_indexes(), bag.pool_size(), removed_value, value, index
+                        );
+                    }
+                    assert_eq!(value, removed_value);
+                }
+            }
+            Lookup => {
+                if let Some(&(value, index)) = values.last() {
+                    let found_value = bag.get(index)
+                        .unwrap_or_else(|| panic!("Couldn't lookup index {:?}", index));
+                    if log {
+                        eprintln!(
+                            "\x1B[1;33mLOOKUP\x1B[0m ({:4}/{:4}) {:04X} == {:04X} @ {:?}",
+                            bag.unused_indexes(), bag.pool_size(), found_value, value, index
+                        );
+                    }
+                    assert_eq!(value, *found_value);
+                }
+            }
+        }
+    }
+}
+
+/// Create a RNG with a set seed.
+pub fn create_rng() -> impl Rng {
+    let seed = [
+        0x4a, 0x94, 0xef, 0x6a,
+        0x5d, 0x23, 0x38, 0x90,
+        0xec, 0x58, 0xdb, 0x09,
+        0xe6, 0x6d, 0xea, 0xd3,
+    ];
+
+    XorShiftRng::from_seed(seed)
+}
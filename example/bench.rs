@@ -0,0 +1,219 @@
+//! Benchmarks of the operations on an indexbag.
+//!
+//! Run with `cargo bench --features bench`.
+
+#[macro_use]
+extern crate criterion;
+extern crate rand;
+extern crate index_bag;
+
+mod actions;
+
+use criterion::{Bencher, Criterion};
+use rand::Rng;
+
+use index_bag::{FreePolicy, IndexBag};
+
+use actions::*;
+
+fn random_ops(c: &mut Criterion) {
+    for &base in &[0, 10, 100, 1000, 10000] {
+        c.bench_function(&format!("random_ops_on_{}", base), |b| bench_random_ops(b, base, 100));
+    }
+}
+
+fn insert(c: &mut Criterion) {
+    for &ops in &[1, 2, 3, 4, 5, 10] {
+        c.bench_function(&format!("insert_{}", ops), |b| bench_ops(b, 10, ops, Insert));
+    }
+    for &base in &[10, 100, 1000, 10000] {
+        c.bench_function(&format!("insert_on_{}", base), |b| bench_ops(b, base, 100, Insert));
+    }
+    c.bench_function("vec_insert", |b| {
+        let mut values = vec![0; 10000];
+        b.iter(move || {
+            for i in 0..100 {
+                values.push(i);
+            }
+        })
+    });
+}
+
+fn remove(c: &mut Criterion) {
+    for &base in &[1000, 10000, 100000] {
+        c.bench_function(&format!("remove_on_{}", base), |b| bench_ops(b, base, 100, Remove));
+    }
+}
+
+fn lookup(c: &mut Criterion) {
+    for &base in &[10, 100, 1000, 10000] {
+        c.bench_function(&format!("lookup_on_{}", base), |b| bench_ops(b, base, 100, Lookup));
+    }
+    c.bench_function("vec_lookup", |b| {
+        let values = vec![1; 10000];
+        b.iter(move || values[..100].iter().sum::<u64>())
+    });
+}
+
+fn iter(c: &mut Criterion) {
+    for &base in &[100, 10000, 1000000] {
+        c.bench_function(&format!("iter_100_of_{}", base), |b| bench_iter(b, base, 100));
+    }
+    c.bench_function("vec_iter", |b| {
+        let values: Vec<u16> = (0..100).collect();
+        b.iter(move || values.iter().map(|&value| value as u64).sum::<u64>())
+    });
+}
+
+fn free_policy(c: &mut Criterion) {
+    let policies = [
+        ("lifo", FreePolicy::Lifo),
+        ("fifo", FreePolicy::Fifo),
+        ("lowest_first", FreePolicy::LowestFirst),
+        ("never_reuse", FreePolicy::NeverReuse),
+    ];
+    for &(name, policy) in &policies {
+        c.bench_function(&format!("churn_{}", name), |b| bench_churn(b, policy));
+        c.bench_function(&format!("lookup_after_churn_{}", name), |b| bench_lookup_after_churn(b, policy));
+    }
+}
+
+fn liveness(c: &mut Criterion) {
+    for &base in &[1000, 100000] {
+        c.bench_function(&format!("contains_on_{}", base), |b| {
+            let (bag, indexes) = half_removed_bag(base);
+            b.iter(move || indexes.iter().filter(|&&index| bag.contains(index)).count())
+        });
+        c.bench_function(&format!("is_valid_on_{}", base), |b| {
+            let (bag, indexes) = half_removed_bag(base);
+            b.iter(move || indexes.iter().filter(|&&index| bag.is_valid(index)).count())
+        });
+        c.bench_function(&format!("get_on_{}", base), |b| {
+            let (bag, indexes) = half_removed_bag(base);
+            b.iter(move || indexes.iter().filter(|&&index| bag.get(index).is_some()).count())
+        });
+    }
+}
+
+fn bench_random_ops(b: &mut Bencher, base: usize, ops: usize) {
+    let mut rng = create_rng();
+    let mut bag = IndexBag::new();
+    let mut values = Vec::with_capacity(base + ops);
+
+    for _ in 0..base {
+        Insert.enact(&mut rng, &mut bag, &mut values, false);
+    }
+
+    b.iter(move || {
+        for _ in 0..ops {
+            let action = rng.choose(ALL_ACTIONS).unwrap();
+            action.enact(&mut rng, &mut bag, &mut values, false);
+        }
+    })
+}
+
+fn bench_ops(b: &mut Bencher, base: usize, ops: usize, op: Action) {
+    let mut bag = IndexBag::new();
+    let mut values = Vec::with_capacity(base + ops);
+    let mut rng = create_rng();
+
+    for _ in 0..(base * 2) {
+        Insert.enact(&mut rng, &mut bag, &mut values, false);
+    }
+    rng.shuffle(values.as_mut_slice());
+
+    for _ in 0..(base) {
+        let (value, index) = values.pop().unwrap();
+        assert_eq!(value, bag.remove(index).unwrap());
+    }
+    rng.shuffle(values.as_mut_slice());
+
+    b.iter(move || {
+        let mut bag = IndexBag::new();
+        let mut values = Vec::new();
+
+        for _ in 0..ops {
+            op.enact(&mut rng, &mut bag, &mut values, false);
+            if let Lookup = op { values.pop(); }
+        }
+    })
+}
+
+fn bench_iter(b: &mut Bencher, base: usize, live: usize) {
+    let mut rng = create_rng();
+    let mut bag = IndexBag::new();
+    let mut values = Vec::with_capacity(base);
+
+    for _ in 0..base {
+        Insert.enact(&mut rng, &mut bag, &mut values, false);
+    }
+    rng.shuffle(values.as_mut_slice());
+
+    for _ in live..base {
+        Remove.enact(&mut rng, &mut bag, &mut values, false);
+    }
+
+    b.iter(move || bag.values().map(|&value| value as u64).sum::<u64>())
+}
+
+/// Fill a bag with the given free policy, then remove and insert random items until most of
+/// them have been replaced.
+fn churned_bag(policy: FreePolicy) -> (impl Rng, IndexBag<u16>, Vec<(u16, index_bag::Index<u16>)>) {
+    let mut rng = create_rng();
+    let mut bag = IndexBag::with_free_policy(policy);
+    let mut values = Vec::with_capacity(10000);
+
+    for _ in 0..10000 {
+        Insert.enact(&mut rng, &mut bag, &mut values, false);
+    }
+    for _ in 0..10 {
+        rng.shuffle(values.as_mut_slice());
+        for _ in 0..1000 {
+            Remove.enact(&mut rng, &mut bag, &mut values, false);
+        }
+        for _ in 0..1000 {
+            Insert.enact(&mut rng, &mut bag, &mut values, false);
+        }
+    }
+
+    (rng, bag, values)
+}
+
+fn bench_churn(b: &mut Bencher, policy: FreePolicy) {
+    let (mut rng, mut bag, mut values) = churned_bag(policy);
+
+    b.iter(move || {
+        for _ in 0..100 {
+            let position = rng.gen_range(0, values.len());
+            let (value, index) = values.swap_remove(position);
+            assert_eq!(bag.remove(index), Some(value));
+        }
+        for _ in 0..100 {
+            Insert.enact(&mut rng, &mut bag, &mut values, false);
+        }
+    })
+}
+
+fn bench_lookup_after_churn(b: &mut Bencher, policy: FreePolicy) {
+    let (_, bag, mut values) = churned_bag(policy);
+    values.sort_by_key(|&(_, index)| index);
+
+    b.iter(move || values.iter().map(|&(_, index)| bag[index] as u64).sum::<u64>())
+}
+
+/// Fill a bag of large items, then remove a random half of them, returning the indexes of every
+/// item that was inserted in a random order.
+fn half_removed_bag(base: usize) -> (IndexBag<[u64; 32]>, Vec<index_bag::Index<[u64; 32]>>) {
+    let mut rng = create_rng();
+    let mut bag = IndexBag::new();
+    let mut indexes: Vec<_> = (0..base).map(|i| bag.insert([i as u64; 32])).collect();
+    rng.shuffle(indexes.as_mut_slice());
+    for &index in &indexes[..base / 2] {
+        bag.remove(index);
+    }
+    rng.shuffle(indexes.as_mut_slice());
+    (bag, indexes)
+}
+
+criterion_group!(benches, random_ops, insert, remove, lookup, iter, free_policy, liveness);
+criterion_main!(benches);
@@ -0,0 +1,169 @@
+//! Bags branded with a unique lifetime.
+//!
+//! A [`BrandedBag`] wraps an [`IndexBag`] for the duration of a closure passed to
+//! [`IndexBag::branded`]. Each call produces a distinct, invariant lifetime `'id` that is carried
+//! by every [`BrandedIndex`] the bag creates, so an index from one branded bag can never be used
+//! with another.
+//!
+//! ```rust,compile_fail
+//! use index_bag::IndexBag;
+//!
+//! let mut first = IndexBag::new();
+//! let mut second = IndexBag::new();
+//! first.branded(|mut first| {
+//!     second.branded(|second| {
+//!         let index = first.insert(12);
+//!         second.get(index);
+//!     })
+//! });
+//! ```
+
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+use super::{Generation, Index, IndexBag, SlotIndex};
+
+/// An invariant lifetime unique to a single branded bag.
+type Brand<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+/// A bag that only accepts indexes that it created.
+///
+/// The underlying [`IndexBag`] can still be read through the branded bag, but items can only be
+/// added, removed, or modified through [`BrandedIndex`] values with the same brand.
+///
+/// ```rust
+/// use index_bag::IndexBag;
+///
+/// let mut bag = IndexBag::new();
+/// let plain = bag.insert(12);
+/// bag.branded(|mut bag| {
+///     let index = bag.insert(13);
+///     assert_eq!(bag.get(index), Some(&13));
+///
+///     let existing = bag.brand(plain).unwrap();
+///     assert_eq!(bag.remove(existing), Some(12));
+///     assert_eq!(bag.brand(plain), None);
+/// });
+/// assert_eq!(bag.get(plain), None);
+/// ```
+pub struct BrandedBag<'id, 'a, T: 'a, G: Generation + 'a = usize, I: 'a = usize> {
+    bag: &'a mut IndexBag<T, G, I>,
+    brand: Brand<'id>,
+}
+
+impl<'id, 'a, T, G: Generation, I: SlotIndex> BrandedBag<'id, 'a, T, G, I> {
+    pub(crate) fn new(bag: &'a mut IndexBag<T, G, I>) -> BrandedBag<'id, 'a, T, G, I> {
+        BrandedBag {
+            bag,
+            brand: PhantomData,
+        }
+    }
+
+    /// Insert an item into the bag.
+    pub fn insert(&mut self, value: T) -> BrandedIndex<'id, T, G, I> {
+        let index = self.bag.insert(value);
+        BrandedIndex::new(index, self.brand)
+    }
+
+    /// Remove an item from the bag.
+    pub fn remove(&mut self, index: BrandedIndex<'id, T, G, I>) -> Option<T> {
+        self.bag.remove(index.index)
+    }
+
+    /// Get a reference to an item in the bag.
+    pub fn get(&self, index: BrandedIndex<'id, T, G, I>) -> Option<&T> {
+        self.bag.get(index.index)
+    }
+
+    /// Get a mutable reference to an item in the bag.
+    pub fn get_mut(&mut self, index: BrandedIndex<'id, T, G, I>) -> Option<&mut T> {
+        self.bag.get_mut(index.index)
+    }
+
+    /// Brand an unbranded [`Index`] if it refers to an item in this bag.
+    pub fn brand(&self, index: Index<T, G, I>) -> Option<BrandedIndex<'id, T, G, I>> {
+        self.bag.get(index)
+            .map(|_| BrandedIndex::new(index, self.brand))
+    }
+}
+
+impl<'id, 'a, T, G: Generation, I> Deref for BrandedBag<'id, 'a, T, G, I> {
+    type Target = IndexBag<T, G, I>;
+
+    fn deref(&self) -> &IndexBag<T, G, I> {
+        self.bag
+    }
+}
+
+/// An index into a [`BrandedBag`].
+pub struct BrandedIndex<'id, T, G: Generation = usize, I = usize> {
+    index: Index<T, G, I>,
+    brand: Brand<'id>,
+}
+
+impl<'id, T, G: Generation, I: SlotIndex> BrandedIndex<'id, T, G, I> {
+    fn new(index: Index<T, G, I>, brand: Brand<'id>) -> BrandedIndex<'id, T, G, I> {
+        BrandedIndex {
+            index,
+            brand,
+        }
+    }
+
+    /// Remove the brand from the index.
+    ///
+    /// The unbranded [`Index`] can outlive the closure that created the branded bag.
+    pub fn unbrand(self) -> Index<T, G, I> {
+        self.index
+    }
+}
+
+impl<'id, T, G: Generation, I: SlotIndex> fmt::Debug for BrandedIndex<'id, T, G, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("BrandedIndex")
+            .field(&self.index)
+            .finish()
+    }
+}
+
+impl<'id, T, G: Generation, I: SlotIndex> Clone for BrandedIndex<'id, T, G, I> {
+    fn clone(&self) -> BrandedIndex<'id, T, G, I> {
+        *self
+    }
+}
+
+impl<'id, T, G: Generation, I: SlotIndex> Copy for BrandedIndex<'id, T, G, I> {}
+
+impl<'id, T, G: Generation, I: SlotIndex> PartialEq for BrandedIndex<'id, T, G, I> {
+    fn eq(&self, other: &BrandedIndex<'id, T, G, I>) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<'id, T, G: Generation, I: SlotIndex> Eq for BrandedIndex<'id, T, G, I> {}
+
+impl<'id, T, G: Generation, I: SlotIndex> PartialOrd for BrandedIndex<'id, T, G, I> {
+    fn partial_cmp(&self, other: &BrandedIndex<'id, T, G, I>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'id, T, G: Generation, I: SlotIndex> Ord for BrandedIndex<'id, T, G, I> {
+    fn cmp(&self, other: &BrandedIndex<'id, T, G, I>) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+impl<'id, T, G: Generation, I: SlotIndex> Hash for BrandedIndex<'id, T, G, I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl<'id, T, G: Generation, I: SlotIndex> From<BrandedIndex<'id, T, G, I>> for Index<T, G, I> {
+    fn from(index: BrandedIndex<'id, T, G, I>) -> Index<T, G, I> {
+        index.unbrand()
+    }
+}
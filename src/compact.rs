@@ -0,0 +1,111 @@
+//! Compact serialization of bags, enabled by the `serde` feature.
+//!
+//! Only the items of the bag are written, in order, so vacant slots take no space. The items are
+//! given new indexes when the bag is read back, which can be found for the indexes of the saved
+//! bag with a [`Remap`](crate::Remap) from [`IndexBag::compact_remap`]. The remap table can be
+//! applied to data holding indexes before it is saved, or saved with the bag and applied once
+//! both are loaded.
+//!
+//! The functions can be used with `#[serde(with = "index_bag::compact")]` on a bag field.
+//!
+//! ```rust
+//! extern crate serde_json;
+//! use index_bag::{compact, IndexBag, Index, Remap, RemapIndexes};
+//!
+//! let mut bag = IndexBag::new();
+//! let first = bag.insert(String::from("first"));
+//! let removed = bag.insert(String::from("removed"));
+//! let last = bag.insert(String::from("last"));
+//! bag.remove(removed);
+//!
+//! let mut saved = Vec::new();
+//! compact::serialize(&bag, &mut serde_json::Serializer::new(&mut saved)).unwrap();
+//! assert_eq!(saved, br#"["first","last"]"#);
+//! let remap = serde_json::to_string(&bag.compact_remap()).unwrap();
+//! let indexes = serde_json::to_string(&[first, removed, last]).unwrap();
+//!
+//! let mut deserializer = serde_json::Deserializer::from_slice(&saved);
+//! let bag: IndexBag<String> = compact::deserialize(&mut deserializer).unwrap();
+//! let remap: Remap<String> = serde_json::from_str(&remap).unwrap();
+//! let mut indexes: [Index<String>; 3] = serde_json::from_str(&indexes).unwrap();
+//! indexes.remap_indexes(&remap);
+//!
+//! assert_eq!(bag.get(indexes[0]).unwrap(), "first");
+//! assert_eq!(bag.get(indexes[1]), None);
+//! assert_eq!(bag.get(indexes[2]).unwrap(), "last");
+//! ```
+
+use alloc::vec::Vec;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::remap::compact_generation;
+use super::{FreePolicy, Generation, IndexBag, SlotIndex};
+
+/// Serialize only the items of a bag.
+pub fn serialize<T, G, I, S>(bag: &IndexBag<T, G, I>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    G: Generation,
+    I: SlotIndex,
+    S: Serializer,
+{
+    serializer.collect_seq(bag.values())
+}
+
+/// Deserialize a bag written by [`serialize`].
+///
+/// The items are stored in the first slots of the bag, in the order they were written. The free
+/// policy of the bag is not saved, so the bag uses the default [`FreePolicy::Lifo`]; use
+/// [`deserialize_with_policy`] to choose another.
+pub fn deserialize<'de, T, G, I, D>(deserializer: D) -> Result<IndexBag<T, G, I>, D::Error>
+where
+    T: Deserialize<'de>,
+    G: Generation,
+    I: SlotIndex,
+    D: Deserializer<'de>,
+{
+    deserialize_with_policy(deserializer, FreePolicy::default())
+}
+
+/// Deserialize a bag written by [`serialize`], reusing its vacant slots in the order given by a
+/// [`FreePolicy`].
+///
+/// ```rust
+/// extern crate serde_json;
+/// use index_bag::{compact, FreePolicy, IndexBag};
+///
+/// let mut bag: IndexBag<i32> = IndexBag::with_free_policy(FreePolicy::Fifo);
+/// bag.insert(12);
+///
+/// let mut saved = Vec::new();
+/// compact::serialize(&bag, &mut serde_json::Serializer::new(&mut saved)).unwrap();
+/// let mut deserializer = serde_json::Deserializer::from_slice(&saved);
+/// let bag: IndexBag<i32> =
+///     compact::deserialize_with_policy(&mut deserializer, FreePolicy::Fifo).unwrap();
+/// assert_eq!(bag.free_policy(), FreePolicy::Fifo);
+/// ```
+pub fn deserialize_with_policy<'de, T, G, I, D>(deserializer: D, policy: FreePolicy)
+    -> Result<IndexBag<T, G, I>, D::Error>
+where
+    T: Deserialize<'de>,
+    G: Generation,
+    I: SlotIndex,
+    D: Deserializer<'de>,
+{
+    let values = Vec::<T>::deserialize(deserializer)?;
+    if let Some(last) = values.len().checked_sub(1) {
+        I::from_usize(last).ok_or_else(|| D::Error::custom("too many items for the index type"))?;
+    }
+
+    let mut bag = IndexBag {
+        first_generation: compact_generation(),
+        ..IndexBag::with_free_policy(policy)
+    };
+    bag.reserve(values.len());
+    for value in values {
+        bag.insert(value);
+    }
+    Ok(bag)
+}
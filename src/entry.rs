@@ -0,0 +1,302 @@
+//! Entries in an [`IndexBag`].
+
+use core::fmt;
+use core::mem;
+
+use super::{Generation, Index, IndexBag, SlotIndex};
+
+/// The state of the slot referred to by an [`Index`].
+///
+/// Created by [`IndexBag::entry`].
+///
+/// ```rust
+/// use index_bag::{Entry, IndexBag, Index};
+///
+/// let mut bag = IndexBag::new();
+/// let index = bag.insert(1);
+///
+/// // Update the item if it is still present, otherwise insert a new one.
+/// bag.entry(index).and_modify(|count| *count += 1).or_insert(1);
+/// assert_eq!(bag.get(index), Some(&2));
+///
+/// bag.remove(index);
+/// let replacement = bag.insert(10);
+///
+/// // A stale index gets a new item, at an index that is returned with it.
+/// let (new_index, count) = bag.entry(index).and_modify(|count| *count += 1).or_insert(1);
+/// assert_eq!(*count, 1);
+/// assert_ne!(new_index, index);
+/// assert_eq!(bag.get(new_index), Some(&1));
+/// bag.remove(new_index);
+///
+/// match bag.entry(index) {
+///     Entry::Stale(entry) => assert_eq!(entry.current_index(), Some(replacement)),
+///     _ => unreachable!(),
+/// }
+///
+/// // The index stays stale once the newer item is removed too.
+/// bag.remove(replacement);
+/// match bag.entry(index) {
+///     Entry::Stale(entry) => assert_eq!(entry.current_index(), None),
+///     _ => unreachable!(),
+/// }
+///
+/// // An index ahead of its slot refers to no item that has been stored, so it is not stale.
+/// let ahead: Index<i32> = Index::from_raw_parts(index.slot(), 5).unwrap();
+/// assert!(matches!(bag.entry(ahead), Entry::Vacant(_)));
+/// ```
+pub enum Entry<'a, T: 'a, G: Generation + 'a = usize, I: 'a = usize> {
+    /// The index refers to an item in the bag.
+    Occupied(OccupiedEntry<'a, T, G, I>),
+    /// The index does not refer to an item in the bag, and its slot has not been reused since.
+    ///
+    /// This includes an index whose generation is ahead of its slot, which no item has been
+    /// stored at yet.
+    Vacant(VacantEntry<'a, T, G, I>),
+    /// The slot the index refers to has been reused for a newer item, which may have been
+    /// removed since.
+    Stale(StaleEntry<'a, T, G, I>),
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> Entry<'a, T, G, I> {
+    /// Get the item referred to by the index along with its index, inserting an item if there
+    /// is none.
+    ///
+    /// If the index is vacant or stale, the item is inserted at a new index, which is generally
+    /// not the one the entry was looked up with. Only a reserved slot keeps its index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an item is inserted, the bag needs a new slot, and every slot that can be referred
+    /// to by the [`SlotIndex`] type is already in use.
+    pub fn or_insert(self, value: T) -> (Index<T, G, I>, &'a mut T) {
+        self.or_insert_with(|| value)
+    }
+
+    /// Get the item referred to by the index along with its index, inserting the result of a
+    /// closure if there is none.
+    ///
+    /// As with [`Entry::or_insert`], an inserted item is generally given a new index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an item is inserted, the bag needs a new slot, and every slot that can be referred
+    /// to by the [`SlotIndex`] type is already in use.
+    pub fn or_insert_with<F: FnOnce() -> T>(self, f: F) -> (Index<T, G, I>, &'a mut T) {
+        let entry = match self {
+            Entry::Occupied(entry) => return (entry.index(), entry.into_mut()),
+            Entry::Vacant(entry) => entry,
+            Entry::Stale(entry) => entry.into_vacant(),
+        };
+        (entry.index(), entry.insert(f()))
+    }
+
+    /// Modify the item referred to by the index, if there is one.
+    pub fn and_modify<F: FnOnce(&mut T)>(mut self, f: F) -> Entry<'a, T, G, I> {
+        if let Entry::Occupied(ref mut entry) = self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, T: fmt::Debug, G: Generation, I: SlotIndex> fmt::Debug for Entry<'a, T, G, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Entry::Occupied(entry) => f.debug_tuple("Occupied").field(entry).finish(),
+            Entry::Vacant(entry) => f.debug_tuple("Vacant").field(entry).finish(),
+            Entry::Stale(entry) => f.debug_tuple("Stale").field(entry).finish(),
+        }
+    }
+}
+
+/// An entry for an item in an [`IndexBag`].
+pub struct OccupiedEntry<'a, T: 'a, G: Generation + 'a = usize, I: 'a = usize> {
+    bag: &'a mut IndexBag<T, G, I>,
+    index: Index<T, G, I>,
+    position: usize,
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> OccupiedEntry<'a, T, G, I> {
+    pub(crate) fn new(bag: &'a mut IndexBag<T, G, I>, index: Index<T, G, I>, position: usize)
+        -> OccupiedEntry<'a, T, G, I>
+    {
+        OccupiedEntry {
+            bag,
+            index,
+            position,
+        }
+    }
+
+    /// The index of the item.
+    pub fn index(&self) -> Index<T, G, I> {
+        self.index
+    }
+
+    /// Get a reference to the item.
+    pub fn get(&self) -> &T {
+        &self.bag.values[self.position]
+    }
+
+    /// Get a mutable reference to the item.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.bag.values[self.position]
+    }
+
+    /// Convert the entry into a mutable reference to the item.
+    pub fn into_mut(self) -> &'a mut T {
+        &mut self.bag.values[self.position]
+    }
+
+    /// Replace the item, returning the original.
+    ///
+    /// The index of the item does not change.
+    pub fn replace(&mut self, value: T) -> T {
+        mem::replace(self.get_mut(), value)
+    }
+
+    /// Remove the item from the bag.
+    pub fn remove(self) -> T {
+        self.bag.remove(self.index).unwrap()
+    }
+}
+
+impl<'a, T: fmt::Debug, G: Generation, I: SlotIndex> fmt::Debug for OccupiedEntry<'a, T, G, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("index", &self.index)
+            .field("value", self.get())
+            .finish()
+    }
+}
+
+/// An entry for an index whose slot has been reused for a newer item.
+pub struct StaleEntry<'a, T: 'a, G: Generation + 'a = usize, I: 'a = usize> {
+    bag: &'a mut IndexBag<T, G, I>,
+    index: Index<T, G, I>,
+    current: Option<Index<T, G, I>>,
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> StaleEntry<'a, T, G, I> {
+    pub(crate) fn new(
+        bag: &'a mut IndexBag<T, G, I>,
+        index: Index<T, G, I>,
+        current: Option<Index<T, G, I>>,
+    ) -> StaleEntry<'a, T, G, I> {
+        StaleEntry {
+            bag,
+            index,
+            current,
+        }
+    }
+
+    /// The stale index.
+    pub fn index(&self) -> Index<T, G, I> {
+        self.index
+    }
+
+    /// The index of the newer item in the slot, or `None` if the slot has been emptied again.
+    ///
+    /// This may refer to a slot that has been reserved but not yet filled.
+    pub fn current_index(&self) -> Option<Index<T, G, I>> {
+        self.current
+    }
+
+    /// Get an entry for the slot that the next item inserted into the bag will be stored at.
+    ///
+    /// The slot is only chosen once the entry is used, so this does not panic when the bag is
+    /// full.
+    pub fn into_vacant(self) -> VacantEntry<'a, T, G, I> {
+        VacantEntry::new(self.bag, None, false)
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> fmt::Debug for StaleEntry<'a, T, G, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StaleEntry")
+            .field("index", &self.index)
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+/// A vacant slot in an [`IndexBag`], whose index is known before an item is inserted into it.
+///
+/// Created by [`IndexBag::vacant_entry`], or by [`IndexBag::entry`] for an index that does not
+/// refer to an item. Unless the index refers to a reserved slot, an entry created by
+/// [`IndexBag::entry`] chooses its slot only when [`VacantEntry::index`] or
+/// [`VacantEntry::insert`] is called.
+///
+/// ```rust
+/// use index_bag::{IndexBag, Index};
+///
+/// struct Node {
+///     name: &'static str,
+///     next: Index<Node>,
+/// }
+///
+/// let mut bag = IndexBag::new();
+/// let entry = bag.vacant_entry();
+/// let this = entry.index();
+/// entry.insert(Node { name: "self", next: this });
+/// assert_eq!(bag.get(this).unwrap().next, this);
+/// ```
+pub struct VacantEntry<'a, T: 'a, G: Generation + 'a = usize, I: 'a = usize> {
+    bag: &'a mut IndexBag<T, G, I>,
+    /// The index of the slot, or `None` if it is the next one the bag gives out.
+    index: Option<Index<T, G, I>>,
+    /// Whether the slot was reserved with [`IndexBag::reserve_index`].
+    reserved: bool,
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> VacantEntry<'a, T, G, I> {
+    pub(crate) fn new(
+        bag: &'a mut IndexBag<T, G, I>,
+        index: Option<Index<T, G, I>>,
+        reserved: bool,
+    ) -> VacantEntry<'a, T, G, I> {
+        VacantEntry {
+            bag,
+            index,
+            reserved,
+        }
+    }
+
+    /// The index that the item will be stored at.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bag needs a new slot and every slot that can be referred to by the
+    /// [`SlotIndex`] type is already in use.
+    pub fn index(&self) -> Index<T, G, I> {
+        self.index.unwrap_or_else(|| {
+            self.bag.next_index()
+                .expect("IndexBag has no more slots available to its index type")
+        })
+    }
+
+    /// Insert an item into the slot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bag needs a new slot and every slot that can be referred to by the
+    /// [`SlotIndex`] type is already in use.
+    pub fn insert(self, value: T) -> &'a mut T {
+        let index = self.index();
+        if self.reserved {
+            let filled = self.bag.fill(index, value);
+            debug_assert!(filled.is_ok());
+        } else {
+            self.bag.occupy(index, value);
+        }
+        self.bag.values.last_mut().unwrap()
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> fmt::Debug for VacantEntry<'a, T, G, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VacantEntry")
+            .field("index", &self.index.or_else(|| self.bag.next_index()))
+            .finish()
+    }
+}
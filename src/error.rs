@@ -0,0 +1,99 @@
+//! Errors returned by an [`IndexBag`](crate::IndexBag).
+
+use core::fmt;
+
+/// An index could not be resolved to an item.
+///
+/// Returned by [`IndexBag::try_get`](crate::IndexBag::try_get) and similar methods to describe why
+/// an [`Index`](crate::Index) does not refer to an item in the bag.
+///
+/// ```rust
+/// use index_bag::{IndexBag, Index, LookupError};
+///
+/// let mut bag = IndexBag::new();
+/// let index = bag.insert(12);
+/// bag.remove(index);
+/// assert_eq!(bag.try_get(index), Err(LookupError::Vacant { generation: 1 }));
+///
+/// let newer = bag.insert(13);
+/// assert_eq!(bag.try_get(index), Err(LookupError::Stale { generation: 1 }));
+///
+/// // The index stays stale once the newer item is removed too.
+/// bag.remove(newer);
+/// assert_eq!(bag.try_get(index), Err(LookupError::Stale { generation: 2 }));
+///
+/// // An index ahead of its slot has not been overtaken by a newer item.
+/// let ahead: Index<i32> = Index::from_raw_parts(index.slot(), 5).unwrap();
+/// assert_eq!(bag.try_get(ahead), Err(LookupError::Vacant { generation: 2 }));
+///
+/// let other = IndexBag::<i32>::new();
+/// assert_eq!(other.try_get(index), Err(LookupError::OutOfRange));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LookupError<G = usize> {
+    /// The index refers to a slot that is past the end of the bag.
+    OutOfRange,
+    /// The index refers to a slot that holds no item for it, and no newer item has been stored in
+    /// the slot since. This includes an index whose generation is ahead of its slot.
+    Vacant {
+        /// The current generation of the slot.
+        generation: G,
+    },
+    /// The index refers to a slot that has been reused for a newer item, which may have been
+    /// removed since.
+    Stale {
+        /// The current generation of the slot.
+        generation: G,
+    },
+}
+
+impl<G: fmt::Debug> fmt::Display for LookupError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LookupError::OutOfRange => {
+                f.write_str("index refers to a slot past the end of the bag")
+            }
+            LookupError::Vacant { generation } => {
+                write!(f, "index has no item in its slot (now at generation {:?})", generation)
+            }
+            LookupError::Stale { generation } => {
+                write!(f, "index is stale (the slot now holds generation {:?})", generation)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<G: fmt::Debug> ::std::error::Error for LookupError<G> {}
+
+/// An item could not be inserted into a bag.
+///
+/// Either memory could not be allocated for the item, or every slot that can be referred to by
+/// the [`SlotIndex`](crate::SlotIndex) type of the bag is already in use. The item that could not
+/// be inserted is returned in the error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapacityError<T> {
+    value: T,
+}
+
+impl<T> CapacityError<T> {
+    pub(crate) fn new(value: T) -> CapacityError<T> {
+        CapacityError {
+            value,
+        }
+    }
+
+    /// Recover the item that could not be inserted.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("insufficient capacity to insert an item into the bag")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> ::std::error::Error for CapacityError<T> {}
@@ -0,0 +1,248 @@
+//! The order in which an [`IndexBag`](crate::IndexBag) reuses vacant slots.
+
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{SlotIndex, SlotState, NO_SLOT};
+
+/// The order in which the vacant slots of a bag are reused.
+///
+/// The policy is chosen when the bag is created with [`IndexBag::with_free_policy`].
+///
+/// [`IndexBag::with_free_policy`]: crate::IndexBag::with_free_policy
+///
+/// ```rust
+/// use index_bag::{FreePolicy, IndexBag};
+///
+/// let mut bag: IndexBag<i32> = IndexBag::with_free_policy(FreePolicy::Fifo);
+/// let first = bag.insert(1);
+/// let second = bag.insert(2);
+/// bag.remove(first);
+/// bag.remove(second);
+///
+/// // The slot that was vacated first is reused first.
+/// let third = bag.insert(3);
+/// assert_eq!(usize::from(third), usize::from(first));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FreePolicy {
+    /// Reuse the most recently vacated slot first.
+    ///
+    /// This is the cheapest policy, but the same few slots take most of the churn, so their
+    /// generations are exhausted soonest.
+    #[default]
+    Lifo,
+    /// Reuse the least recently vacated slot first, spreading the churn evenly over the slots.
+    Fifo,
+    /// Reuse the vacant slot with the lowest index first, keeping the items close to the start of
+    /// the bag.
+    ///
+    /// ```rust
+    /// use index_bag::{FreePolicy, IndexBag};
+    ///
+    /// let mut bag: IndexBag<i32> = IndexBag::with_free_policy(FreePolicy::LowestFirst);
+    /// let indexes: Vec<_> = (0..5).map(|i| bag.insert(i)).collect();
+    /// for &slot in &[3, 1, 4] {
+    ///     bag.remove(indexes[slot]);
+    /// }
+    ///
+    /// // The slots are reused from the lowest up, whatever order they were vacated in.
+    /// let reused: Vec<usize> = (0..3).map(|i| usize::from(bag.insert(i))).collect();
+    /// assert_eq!(reused, vec![1, 3, 4]);
+    /// ```
+    LowestFirst,
+    /// Never reuse a slot once it has been vacated.
+    ///
+    /// The bag grows with every insertion, until its items are moved back to the first slots by
+    /// [`IndexBag::compact`](crate::IndexBag::compact). Vacated slots at the end of the bag are also
+    /// discarded by [`IndexBag::shrink_to_fit`](crate::IndexBag::shrink_to_fit).
+    ///
+    /// ```rust
+    /// use index_bag::{FreePolicy, IndexBag};
+    ///
+    /// let mut bag: IndexBag<i32> = IndexBag::with_free_policy(FreePolicy::NeverReuse);
+    /// let indexes: Vec<_> = (0..4).map(|i| bag.insert(i)).collect();
+    /// for &index in &indexes[..3] {
+    ///     bag.remove(index);
+    /// }
+    /// bag.insert(4);
+    /// assert_eq!(bag.pool_size(), 5);
+    ///
+    /// bag.compact(|_, _| {});
+    /// assert_eq!(bag.pool_size(), 2);
+    ///
+    /// let last = bag.insert(5);
+    /// bag.remove(last);
+    /// assert_eq!(bag.pool_size(), 3);
+    /// bag.shrink_to_fit();
+    /// assert_eq!(bag.pool_size(), 2);
+    /// assert_eq!(bag.get(last), None);
+    /// ```
+    NeverReuse,
+}
+
+/// The vacant slots of a bag, in the order given by its [`FreePolicy`].
+///
+/// Stacks and queues are threaded through the vacant slots themselves, each of which holds the
+/// position of the next slot in the list.
+#[derive(Debug, Clone)]
+pub(crate) enum FreeList<I> {
+    Lifo {
+        head: usize,
+        len: usize,
+    },
+    Fifo {
+        head: usize,
+        tail: usize,
+        len: usize,
+    },
+    LowestFirst(BinaryHeap<Reverse<I>>),
+    NeverReuse,
+}
+
+impl<I: SlotIndex> FreeList<I> {
+    pub(crate) fn new(policy: FreePolicy) -> FreeList<I> {
+        match policy {
+            FreePolicy::Lifo => FreeList::Lifo {
+                head: NO_SLOT,
+                len: 0,
+            },
+            FreePolicy::Fifo => FreeList::Fifo {
+                head: NO_SLOT,
+                tail: NO_SLOT,
+                len: 0,
+            },
+            FreePolicy::LowestFirst => FreeList::LowestFirst(BinaryHeap::new()),
+            FreePolicy::NeverReuse => FreeList::NeverReuse,
+        }
+    }
+
+    pub(crate) fn policy(&self) -> FreePolicy {
+        match self {
+            FreeList::Lifo { .. } => FreePolicy::Lifo,
+            FreeList::Fifo { .. } => FreePolicy::Fifo,
+            FreeList::LowestFirst(_) => FreePolicy::LowestFirst,
+            FreeList::NeverReuse => FreePolicy::NeverReuse,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match *self {
+            FreeList::Lifo { len, .. } | FreeList::Fifo { len, .. } => len,
+            FreeList::LowestFirst(ref indexes) => indexes.len(),
+            FreeList::NeverReuse => 0,
+        }
+    }
+
+    /// The slot that will be reused next.
+    pub(crate) fn peek(&self) -> Option<I> {
+        match *self {
+            FreeList::Lifo { head, .. } | FreeList::Fifo { head, .. } => slot_index(head),
+            FreeList::LowestFirst(ref indexes) => indexes.peek().map(|&Reverse(index)| index),
+            FreeList::NeverReuse => None,
+        }
+    }
+
+    /// Take the slot returned by [`FreeList::peek`] out of the list.
+    ///
+    /// The state of the slot is left for the caller to replace.
+    pub(crate) fn pop(&mut self, states: &[SlotState]) -> Option<I> {
+        match self {
+            FreeList::Lifo { head, len } => {
+                let index = slot_index(*head)?;
+                *head = next_free(states[*head]);
+                *len -= 1;
+                Some(index)
+            }
+            FreeList::Fifo { head, tail, len } => {
+                let index = slot_index(*head)?;
+                *head = next_free(states[*head]);
+                if *head == NO_SLOT {
+                    *tail = NO_SLOT;
+                }
+                *len -= 1;
+                Some(index)
+            }
+            FreeList::LowestFirst(indexes) => indexes.pop().map(|Reverse(index)| index),
+            FreeList::NeverReuse => None,
+        }
+    }
+
+    /// Add a vacant slot to the list, returning `false` if the policy never reuses slots.
+    pub(crate) fn push(&mut self, index: I, states: &mut [SlotState]) -> bool {
+        let position = index.into_usize();
+        match self {
+            FreeList::Lifo { head, len } => {
+                states[position] = SlotState::Vacant { next_free: *head };
+                *head = position;
+                *len += 1;
+            }
+            FreeList::Fifo { head, tail, len } => {
+                states[position] = SlotState::Vacant { next_free: NO_SLOT };
+                if *tail == NO_SLOT {
+                    *head = position;
+                } else {
+                    states[*tail] = SlotState::Vacant { next_free: position };
+                }
+                *tail = position;
+                *len += 1;
+            }
+            FreeList::LowestFirst(indexes) => indexes.push(Reverse(index)),
+            FreeList::NeverReuse => return false,
+        }
+        true
+    }
+
+    /// Empty the list, leaving the slots that were in it untouched.
+    pub(crate) fn clear(&mut self) {
+        *self = FreeList::new(self.policy());
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        if let FreeList::LowestFirst(indexes) = self {
+            indexes.shrink_to_fit();
+        }
+    }
+
+    /// The slots in the list, in an order that rebuilds the list when they are pushed again.
+    pub(crate) fn to_vec(&self, states: &[SlotState]) -> Vec<I> {
+        let mut indexes = Vec::with_capacity(self.len());
+        match *self {
+            FreeList::Lifo { head, .. } | FreeList::Fifo { head, .. } => {
+                let mut position = head;
+                while let Some(index) = slot_index(position) {
+                    indexes.push(index);
+                    position = next_free(states[position]);
+                }
+                if let FreeList::Lifo { .. } = *self {
+                    indexes.reverse();
+                }
+            }
+            FreeList::LowestFirst(ref heap) => indexes.extend(heap.iter().map(|&Reverse(index)| index)),
+            FreeList::NeverReuse => {}
+        }
+        indexes
+    }
+}
+
+/// The position of the slot after a vacant slot in the free list.
+fn next_free(state: SlotState) -> usize {
+    match state {
+        SlotState::Vacant { next_free } => next_free,
+        _ => unreachable!("slot in the free list is not vacant"),
+    }
+}
+
+/// The index of the slot at a position, or `None` at the end of the free list.
+fn slot_index<I: SlotIndex>(position: usize) -> Option<I> {
+    if position == NO_SLOT {
+        None
+    } else {
+        Some(I::from_usize(position).expect("slot in the free list is out of range"))
+    }
+}
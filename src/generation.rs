@@ -0,0 +1,112 @@
+//! Generation counters for the slots of an [`IndexBag`](crate::IndexBag).
+
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::num::{NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize};
+
+/// A generation counter.
+///
+/// Every slot in an [`IndexBag`](crate::IndexBag) counts the items that have been stored in it,
+/// and each [`Index`](crate::Index) records the count at the time it was created so that it will
+/// not resolve to any later item stored in the same slot.
+///
+/// The width of the counter is chosen by the second type parameter of the bag. When the counter of
+/// a slot is exhausted the slot is retired rather than wrapping around to an earlier generation, so
+/// it is never reused and an old index can never resolve to a new item.
+///
+/// An index stores its generation offset by one in the matching [`Generation::NonZero`] type, so
+/// that `Option<Index>` is no larger than `Index`. The largest value of each integer type is
+/// therefore never used as a generation, and is instead given to slots whose counter is exhausted.
+///
+/// ```rust
+/// use index_bag::IndexBag;
+///
+/// let mut bag: IndexBag<u32, u8> = IndexBag::default();
+/// let first = bag.insert(0);
+/// let mut index = first;
+/// for i in 1..255 {
+///     assert_eq!(bag.remove(index), Some(i - 1));
+///     index = bag.insert(i);
+///     assert_eq!(bag.pool_size(), 1);
+/// }
+///
+/// // The slot has been used for every generation and is retired on removal.
+/// assert_eq!(bag.remove(index), Some(254));
+/// assert_eq!(bag.unused_indexes(), 0);
+///
+/// let next = bag.insert(255);
+/// assert_eq!(bag.pool_size(), 2);
+/// assert_eq!(bag.get(first), None);
+/// assert_eq!(bag.get(index), None);
+/// assert_eq!(bag.get(next), Some(&255));
+/// ```
+pub trait Generation: Copy + Eq + Ord + Hash + Debug {
+    /// The type used to store the generation in an index.
+    type NonZero: Copy + Eq + Ord + Hash + Debug;
+
+    /// The generation of a newly created slot.
+    fn first() -> Self;
+
+    /// The generation after this one, or `None` if the counter is exhausted.
+    fn next(self) -> Option<Self>;
+
+    /// The generation of a retired slot whose counter is exhausted.
+    ///
+    /// It must be past every generation returned by [`Generation::next`], and have no non-zero
+    /// representation, so that no index ever refers to it.
+    fn exhausted() -> Self;
+
+    /// Offset the generation into its non-zero representation, or `None` if it is the largest
+    /// value of its type.
+    fn try_into_non_zero(self) -> Option<Self::NonZero>;
+
+    /// Offset the generation into its non-zero representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the generation is the largest value of its type.
+    fn into_non_zero(self) -> Self::NonZero {
+        self.try_into_non_zero().expect("generation out of range")
+    }
+
+    /// Recover the generation from its non-zero representation.
+    fn from_non_zero(generation: Self::NonZero) -> Self;
+}
+
+macro_rules! impl_generation {
+    ($($ty:ty => $non_zero:ty),*) => {
+        $(
+            impl Generation for $ty {
+                type NonZero = $non_zero;
+
+                fn first() -> $ty {
+                    0
+                }
+
+                fn next(self) -> Option<$ty> {
+                    self.checked_add(2).map(|_| self + 1)
+                }
+
+                fn exhausted() -> $ty {
+                    <$ty>::MAX
+                }
+
+                fn try_into_non_zero(self) -> Option<$non_zero> {
+                    <$non_zero>::new(self.checked_add(1)?)
+                }
+
+                fn from_non_zero(generation: $non_zero) -> $ty {
+                    generation.get() - 1
+                }
+            }
+        )*
+    };
+}
+
+impl_generation!(
+    u8 => NonZeroU8,
+    u16 => NonZeroU16,
+    u32 => NonZeroU32,
+    u64 => NonZeroU64,
+    usize => NonZeroUsize
+);
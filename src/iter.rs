@@ -0,0 +1,357 @@
+//! Iterators over the items in an [`IndexBag`].
+
+use core::fmt;
+use core::iter::FusedIterator;
+use core::slice;
+use alloc::vec;
+
+use super::{Generation, Index, IndexBag, SlotIndex};
+
+/// An iterator over the indexes and items in an [`IndexBag`].
+///
+/// Created by [`IndexBag::iter`].
+#[derive(Debug)]
+pub struct Iter<'a, T: 'a, G: Generation + 'a = usize, I: 'a = usize> {
+    keys: slice::Iter<'a, Index<T, G, I>>,
+    values: slice::Iter<'a, T>,
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> Iter<'a, T, G, I> {
+    pub(crate) fn new(bag: &'a IndexBag<T, G, I>) -> Iter<'a, T, G, I> {
+        Iter {
+            keys: bag.keys.iter(),
+            values: bag.values.iter(),
+        }
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> Clone for Iter<'a, T, G, I> {
+    fn clone(&self) -> Iter<'a, T, G, I> {
+        Iter {
+            keys: self.keys.clone(),
+            values: self.values.clone(),
+        }
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> Iterator for Iter<'a, T, G, I> {
+    type Item = (Index<T, G, I>, &'a T);
+
+    fn next(&mut self) -> Option<(Index<T, G, I>, &'a T)> {
+        Some((*self.keys.next()?, self.values.next()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> ExactSizeIterator for Iter<'a, T, G, I> {}
+
+impl<'a, T, G: Generation, I: SlotIndex> FusedIterator for Iter<'a, T, G, I> {}
+
+/// An iterator over the indexes and mutable references to the items in an [`IndexBag`].
+///
+/// Created by [`IndexBag::iter_mut`].
+#[derive(Debug)]
+pub struct IterMut<'a, T: 'a, G: Generation + 'a = usize, I: 'a = usize> {
+    keys: slice::Iter<'a, Index<T, G, I>>,
+    values: slice::IterMut<'a, T>,
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> IterMut<'a, T, G, I> {
+    pub(crate) fn new(bag: &'a mut IndexBag<T, G, I>) -> IterMut<'a, T, G, I> {
+        IterMut {
+            keys: bag.keys.iter(),
+            values: bag.values.iter_mut(),
+        }
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> Iterator for IterMut<'a, T, G, I> {
+    type Item = (Index<T, G, I>, &'a mut T);
+
+    fn next(&mut self) -> Option<(Index<T, G, I>, &'a mut T)> {
+        Some((*self.keys.next()?, self.values.next()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> ExactSizeIterator for IterMut<'a, T, G, I> {}
+
+impl<'a, T, G: Generation, I: SlotIndex> FusedIterator for IterMut<'a, T, G, I> {}
+
+/// An iterator that moves the indexes and items out of an [`IndexBag`].
+///
+/// Created by the [`IntoIterator`] implementation for [`IndexBag`].
+#[derive(Debug)]
+pub struct IntoIter<T, G: Generation = usize, I = usize> {
+    keys: vec::IntoIter<Index<T, G, I>>,
+    values: vec::IntoIter<T>,
+}
+
+impl<T, G: Generation, I: SlotIndex> IntoIter<T, G, I> {
+    pub(crate) fn new(bag: IndexBag<T, G, I>) -> IntoIter<T, G, I> {
+        IntoIter {
+            keys: bag.keys.into_iter(),
+            values: bag.values.into_iter(),
+        }
+    }
+}
+
+impl<T, G: Generation, I: SlotIndex> Iterator for IntoIter<T, G, I> {
+    type Item = (Index<T, G, I>, T);
+
+    fn next(&mut self) -> Option<(Index<T, G, I>, T)> {
+        Some((self.keys.next()?, self.values.next()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<T, G: Generation, I: SlotIndex> ExactSizeIterator for IntoIter<T, G, I> {}
+
+impl<T, G: Generation, I: SlotIndex> FusedIterator for IntoIter<T, G, I> {}
+
+/// An iterator that removes the items from an [`IndexBag`].
+///
+/// Created by [`IndexBag::drain`]. Every slot in the bag is vacated when the iterator is created,
+/// and any items that have not been yielded when the iterator is dropped are dropped with it.
+#[derive(Debug)]
+pub struct Drain<'a, T: 'a, G: Generation + 'a = usize, I: 'a = usize> {
+    keys: vec::Drain<'a, Index<T, G, I>>,
+    values: vec::Drain<'a, T>,
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> Drain<'a, T, G, I> {
+    pub(crate) fn new(bag: &'a mut IndexBag<T, G, I>) -> Drain<'a, T, G, I> {
+        for position in 0..bag.keys.len() {
+            let key = bag.keys[position];
+            if bag.vacate_slot(key.slot()) {
+                bag.release(key.index);
+            }
+        }
+        Drain {
+            keys: bag.keys.drain(..),
+            values: bag.values.drain(..),
+        }
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> Iterator for Drain<'a, T, G, I> {
+    type Item = (Index<T, G, I>, T);
+
+    fn next(&mut self) -> Option<(Index<T, G, I>, T)> {
+        Some((self.keys.next()?, self.values.next()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> ExactSizeIterator for Drain<'a, T, G, I> {}
+
+impl<'a, T, G: Generation, I: SlotIndex> FusedIterator for Drain<'a, T, G, I> {}
+
+/// An iterator that removes the items matching a predicate from an [`IndexBag`].
+///
+/// Created by [`IndexBag::extract_if`].
+pub struct ExtractIf<'a, T: 'a, F, G: Generation + 'a = usize, I: 'a = usize> {
+    bag: &'a mut IndexBag<T, G, I>,
+    position: usize,
+    predicate: F,
+}
+
+impl<'a, T, F, G: Generation, I: SlotIndex> ExtractIf<'a, T, F, G, I>
+where
+    F: FnMut(Index<T, G, I>, &mut T) -> bool,
+{
+    pub(crate) fn new(bag: &'a mut IndexBag<T, G, I>, predicate: F) -> ExtractIf<'a, T, F, G, I> {
+        ExtractIf {
+            bag,
+            position: 0,
+            predicate,
+        }
+    }
+}
+
+impl<'a, T, F, G: Generation, I: SlotIndex> Iterator for ExtractIf<'a, T, F, G, I>
+where
+    F: FnMut(Index<T, G, I>, &mut T) -> bool,
+{
+    type Item = (Index<T, G, I>, T);
+
+    fn next(&mut self) -> Option<(Index<T, G, I>, T)> {
+        while self.position < self.bag.len() {
+            let index = self.bag.keys[self.position];
+            if (self.predicate)(index, &mut self.bag.values[self.position]) {
+                return Some(self.bag.remove_at(self.position));
+            }
+            self.position += 1;
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.bag.len() - self.position))
+    }
+}
+
+impl<'a, T, F, G: Generation, I: SlotIndex> FusedIterator for ExtractIf<'a, T, F, G, I>
+where
+    F: FnMut(Index<T, G, I>, &mut T) -> bool,
+{}
+
+impl<'a, T: fmt::Debug, F, G: Generation, I: SlotIndex> fmt::Debug for ExtractIf<'a, T, F, G, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExtractIf")
+            .field("bag", &self.bag)
+            .field("position", &self.position)
+            .finish()
+    }
+}
+
+/// An iterator over the indexes of the items in an [`IndexBag`].
+///
+/// Created by [`IndexBag::keys`].
+#[derive(Debug)]
+pub struct Keys<'a, T: 'a, G: Generation + 'a = usize, I: 'a = usize> {
+    inner: slice::Iter<'a, Index<T, G, I>>,
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> Keys<'a, T, G, I> {
+    pub(crate) fn new(bag: &'a IndexBag<T, G, I>) -> Keys<'a, T, G, I> {
+        Keys {
+            inner: bag.keys.iter(),
+        }
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> Clone for Keys<'a, T, G, I> {
+    fn clone(&self) -> Keys<'a, T, G, I> {
+        Keys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> Iterator for Keys<'a, T, G, I> {
+    type Item = Index<T, G, I>;
+
+    fn next(&mut self) -> Option<Index<T, G, I>> {
+        self.inner.next().cloned()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> ExactSizeIterator for Keys<'a, T, G, I> {}
+
+impl<'a, T, G: Generation, I: SlotIndex> FusedIterator for Keys<'a, T, G, I> {}
+
+/// An iterator over the items in an [`IndexBag`].
+///
+/// Created by [`IndexBag::values`].
+#[derive(Debug)]
+pub struct Values<'a, T: 'a> {
+    inner: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Values<'a, T> {
+    pub(crate) fn new<G: Generation, I>(bag: &'a IndexBag<T, G, I>) -> Values<'a, T> {
+        Values {
+            inner: bag.values.iter(),
+        }
+    }
+}
+
+impl<'a, T> Clone for Values<'a, T> {
+    fn clone(&self) -> Values<'a, T> {
+        Values {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Values<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Values<'a, T> {}
+
+impl<'a, T> FusedIterator for Values<'a, T> {}
+
+/// An iterator over mutable references to the items in an [`IndexBag`].
+///
+/// Created by [`IndexBag::values_mut`].
+#[derive(Debug)]
+pub struct ValuesMut<'a, T: 'a> {
+    inner: slice::IterMut<'a, T>,
+}
+
+impl<'a, T> ValuesMut<'a, T> {
+    pub(crate) fn new<G: Generation, I>(bag: &'a mut IndexBag<T, G, I>) -> ValuesMut<'a, T> {
+        ValuesMut {
+            inner: bag.values.iter_mut(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for ValuesMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for ValuesMut<'a, T> {}
+
+impl<'a, T> FusedIterator for ValuesMut<'a, T> {}
+
+impl<T, G: Generation, I: SlotIndex> IntoIterator for IndexBag<T, G, I> {
+    type Item = (Index<T, G, I>, T);
+    type IntoIter = IntoIter<T, G, I>;
+
+    fn into_iter(self) -> IntoIter<T, G, I> {
+        IntoIter::new(self)
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> IntoIterator for &'a IndexBag<T, G, I> {
+    type Item = (Index<T, G, I>, &'a T);
+    type IntoIter = Iter<'a, T, G, I>;
+
+    fn into_iter(self) -> Iter<'a, T, G, I> {
+        Iter::new(self)
+    }
+}
+
+impl<'a, T, G: Generation, I: SlotIndex> IntoIterator for &'a mut IndexBag<T, G, I> {
+    type Item = (Index<T, G, I>, &'a mut T);
+    type IntoIter = IterMut<'a, T, G, I>;
+
+    fn into_iter(self) -> IterMut<'a, T, G, I> {
+        IterMut::new(self)
+    }
+}
@@ -17,16 +17,51 @@
 //! assert_eq!(bag.remove(index), Some(12));
 //! assert_eq!(bag.remove(index), None);
 //! ```
+//!
+//! The crate is `no_std` and only requires `alloc`. The `std` feature, enabled by default,
+//! implements `std::error::Error` for the error types. The `serde` feature implements
+//! `Serialize` and `Deserialize` for [`IndexBag`] and [`Index`], and adds the `compact` module
+//! for saving bags without their vacant slots.
 #![no_std]
-#![feature(alloc)]
-#![feature(test)]
 
 extern crate alloc;
-#[cfg(test)]
-extern crate test;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::hint;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops;
+
+use free_list::FreeList;
+
+mod brand;
+#[cfg(feature = "serde")]
+pub mod compact;
+mod entry;
+mod error;
+mod free_list;
+mod generation;
+mod iter;
+mod remap;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod slot_index;
 
-use alloc::prelude::*;
-use core::mem::swap;
+pub use brand::{BrandedBag, BrandedIndex};
+pub use entry::{Entry, OccupiedEntry, StaleEntry, VacantEntry};
+pub use error::{CapacityError, LookupError};
+pub use free_list::FreePolicy;
+pub use generation::Generation;
+pub use iter::{Drain, ExtractIf, IntoIter, Iter, IterMut, Keys, Values, ValuesMut};
+pub use remap::{Remap, RemapIndexes};
+pub use slot_index::SlotIndex;
 
 /// The bag of values.
 ///
@@ -39,18 +74,553 @@ use core::mem::swap;
 /// assert_eq!(bag.remove(index), Some(12));
 /// assert_eq!(bag.remove(index), None);
 /// ```
-#[derive(Debug, Clone)]
-pub struct IndexBag<T> {
-    data: Vec<(Option<T>, usize)>,
-    free_indexes: Vec<usize>,
+///
+/// Items are stored densely in the order they were inserted, with the last item taking the place
+/// of any item that is removed. Each [`Index`] refers to a slot that records where its item is
+/// stored, so iterating over the bag only visits the stored items regardless of how many slots
+/// are vacant.
+///
+/// The width of the generation counter of each slot can be chosen with the second type parameter
+/// (see [`Generation`]), and the width of the integer used to refer to each slot with the third
+/// (see [`SlotIndex`]). The order in which vacant slots are reused is chosen with a
+/// [`FreePolicy`] when the bag is created.
+///
+/// Items of any type can be stored, including those that can not be printed.
+///
+/// ```rust
+/// use index_bag::IndexBag;
+///
+/// let mut bag: IndexBag<Box<dyn Fn(i32) -> i32>> = IndexBag::new();
+/// let double = bag.insert(Box::new(|x| x * 2));
+/// let square = bag.insert(Box::new(|x| x * x));
+/// assert_eq!(bag[double](3), 6);
+/// assert_eq!(bag[square](3), 9);
+/// ```
+///
+/// When the items can be printed, the bag prints each item with its index.
+///
+/// ```rust
+/// use index_bag::IndexBag;
+///
+/// let mut bag = IndexBag::new();
+/// bag.insert(12);
+/// let index = bag.insert(13);
+/// bag.remove(index);
+/// assert_eq!(format!("{:?}", bag), "{Index { index: 0, generation: 0 }: 12}");
+/// ```
+///
+/// With the `serde` feature, a bag is serialized along with the generations of its vacant slots,
+/// so indexes saved with it resolve the same way once both are loaded again.
+///
+/// ```rust
+/// # #[cfg(feature = "serde")]
+/// # fn main() {
+/// extern crate serde_json;
+/// use index_bag::{IndexBag, Index};
+///
+/// let mut bag = IndexBag::new();
+/// let first = bag.insert(12);
+/// let second = bag.insert(13);
+/// bag.remove(first);
+///
+/// let saved = serde_json::to_string(&(&bag, [first, second])).unwrap();
+/// let (mut bag, [first, second]): (IndexBag<i32>, [Index<i32>; 2]) =
+///     serde_json::from_str(&saved).unwrap();
+/// assert_eq!(bag.get(first), None);
+/// assert_eq!(bag.get(second), Some(&13));
+///
+/// // The freed slot is reused with a new generation, just as before saving.
+/// let third = bag.insert(14);
+/// assert_eq!(usize::from(third), usize::from(first));
+/// assert_eq!(bag.get(first), None);
+/// assert_eq!(bag.get(third), Some(&14));
+///
+/// // The free list must agree with the vacant slots.
+/// let invalid = r#"{"slots":[["Vacant",1]],"items":[],"free_indexes":[],"first_generation":0}"#;
+/// assert!(serde_json::from_str::<IndexBag<i32>>(invalid).is_err());
+/// # }
+/// # #[cfg(not(feature = "serde"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct IndexBag<T, G: Generation = usize, I = usize> {
+    /// The generation of each slot, kept apart from the states so that indexes can be checked
+    /// without reading anything else.
+    generations: Vec<G>,
+    states: Vec<SlotState>,
+    values: Vec<T>,
+    keys: Vec<Index<T, G, I>>,
+    free_indexes: FreeList<I>,
+    /// The number of leading slots that are up to date. Later slots were emptied by
+    /// [`IndexBag::reset`], and are brought up to date as they are reused.
+    valid_slots: usize,
+    /// The generation given to new slots, which is past that of any slot that has been discarded.
+    first_generation: G,
+}
+
+/// A slot referred to by an [`Index`], as read from the generations and states of a bag.
+#[derive(Debug, Clone, Copy)]
+struct Slot<G> {
+    state: SlotState,
+    generation: G,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlotState {
+    /// The slot holds the item at the given position in the dense storage.
+    Occupied(usize),
+    /// The slot is empty and can be reused. Slots in the free list hold the position of the next
+    /// slot in the list, or [`NO_SLOT`].
+    Vacant { next_free: usize },
+    /// The slot is empty but has been reserved for an item that has not been provided yet.
+    Reserved,
+    /// The slot is empty and can never be reused, because its generation is exhausted or the
+    /// bag never reuses slots.
+    Retired,
 }
 
-impl<T: ::core::fmt::Debug> IndexBag<T> {
+/// The position used to end the free list, which no slot can have.
+const NO_SLOT: usize = usize::MAX;
+
+impl SlotState {
+    fn is_vacant(self) -> bool {
+        matches!(self, SlotState::Vacant { .. })
+    }
+}
+
+impl<G: Generation> Slot<G> {
+    /// Vacate the slot, returning whether it can be reused.
+    fn vacate(&mut self) -> bool {
+        if let Some(generation) = self.generation.next() {
+            self.generation = generation;
+            self.state = SlotState::Vacant { next_free: NO_SLOT };
+            true
+        } else {
+            self.generation = G::exhausted();
+            self.state = SlotState::Retired;
+            false
+        }
+    }
+
+    /// Whether an item newer than the one an index with the given generation referred to has been
+    /// stored in the slot, or reserved in it.
+    ///
+    /// A slot that is empty has held the generation before its own at most, since its generation
+    /// is advanced when it is vacated.
+    fn reused_since(self, generation: G) -> bool {
+        match self.state {
+            SlotState::Occupied(_) | SlotState::Reserved => self.generation > generation,
+            _ => generation.next().is_some_and(|next| self.generation > next),
+        }
+    }
+
+    /// Whether the slot is empty and can be given to a new item when the bag is compacted or
+    /// discarded when it is shrunk. Slots retired only because the bag never reuses slots count,
+    /// but slots whose generation is exhausted do not.
+    fn reclaimable(self) -> bool {
+        match self.state {
+            SlotState::Vacant { .. } => true,
+            SlotState::Retired => self.generation != G::exhausted(),
+            _ => false,
+        }
+    }
+
+    /// The slot as it is once any item or reservation in it has been removed.
+    fn cleared(mut self) -> Slot<G> {
+        if let SlotState::Occupied(_) | SlotState::Reserved = self.state {
+            self.vacate();
+        }
+        self
+    }
+}
+
+impl<T> IndexBag<T> {
     /// Create an empty bag.
     pub fn new() -> IndexBag<T> {
+        IndexBag::default()
+    }
+
+    /// Create an empty bag with space for at least `capacity` items.
+    pub fn with_capacity(capacity: usize) -> IndexBag<T> {
+        let mut bag = IndexBag::default();
+        bag.reserve(capacity);
+        bag
+    }
+}
+
+impl<T, G: Generation, I: SlotIndex> Default for IndexBag<T, G, I> {
+    fn default() -> IndexBag<T, G, I> {
+        IndexBag::with_free_policy(FreePolicy::default())
+    }
+}
+
+impl<T, G: Generation, I: SlotIndex> IndexBag<T, G, I> {
+    /// Create an empty bag that reuses vacant slots in the order given by a [`FreePolicy`].
+    pub fn with_free_policy(policy: FreePolicy) -> IndexBag<T, G, I> {
         IndexBag {
-            data: Vec::new(),
-            free_indexes: Vec::new(),
+            generations: Vec::new(),
+            states: Vec::new(),
+            values: Vec::new(),
+            keys: Vec::new(),
+            free_indexes: FreeList::new(policy),
+            valid_slots: 0,
+            first_generation: G::first(),
+        }
+    }
+
+    /// The order in which the bag reuses vacant slots.
+    pub fn free_policy(&self) -> FreePolicy {
+        self.free_indexes.policy()
+    }
+
+    /// The number of items in the bag.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether the bag contains no items.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The number of items the bag can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        let slot_capacity = self.generations.capacity().min(self.states.capacity());
+        let slots = self.len() + self.unused_indexes() + slot_capacity - self.states.len();
+        self.values.capacity()
+            .min(self.keys.capacity())
+            .min(slots)
+    }
+
+    /// Reserve space for at least `additional` more items to be inserted.
+    pub fn reserve(&mut self, additional: usize) {
+        self.values.reserve(additional);
+        self.keys.reserve(additional);
+        let new_slots = additional.saturating_sub(self.unused_indexes());
+        self.generations.reserve(new_slots);
+        self.states.reserve(new_slots);
+    }
+
+    /// Shrink the storage of the bag as much as possible.
+    ///
+    /// Vacant slots at the end of the bag are discarded. Slots created in their place later are
+    /// given a generation past that of any discarded slot, so an [`Index`] to an item that was
+    /// removed never resolves again.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::with_capacity(10);
+    /// let first = bag.insert(12);
+    /// let second = bag.insert(13);
+    /// bag.remove(second);
+    ///
+    /// bag.shrink_to_fit();
+    /// assert_eq!(bag.pool_size(), 1);
+    /// assert_eq!(bag.unused_indexes(), 0);
+    /// assert_eq!(bag.capacity(), 1);
+    ///
+    /// let third = bag.insert(14);
+    /// assert_eq!(bag.get(second), None);
+    /// assert_eq!(bag.get(first), Some(&12));
+    /// assert_eq!(bag.get(third), Some(&14));
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.update_cleared_slots();
+        let free_indexes = self.free_indexes.to_vec(&self.states);
+        self.free_indexes.clear();
+        self.truncate_reclaimable_slots();
+        for index in free_indexes {
+            if index.into_usize() < self.states.len() {
+                self.release(index);
+            }
+        }
+
+        self.generations.shrink_to_fit();
+        self.states.shrink_to_fit();
+        self.values.shrink_to_fit();
+        self.keys.shrink_to_fit();
+        self.free_indexes.shrink_to_fit();
+    }
+
+    /// Discard the reclaimable slots at the end of the bag, without updating the free list.
+    ///
+    /// Every slot must be up to date.
+    fn truncate_reclaimable_slots(&mut self) {
+        while let Some(position) = self.states.len().checked_sub(1) {
+            if !self.stored_slot(position).reclaimable() {
+                break;
+            }
+            self.states.pop();
+            let generation = self.generations.pop().unwrap();
+            self.first_generation = self.first_generation.max(generation);
+        }
+        self.valid_slots = self.states.len();
+    }
+
+    /// Bring the slots emptied by [`IndexBag::reset`] up to date, adding them to the free list.
+    fn update_cleared_slots(&mut self) {
+        for position in (self.valid_slots..self.states.len()).rev() {
+            let slot = self.stored_slot(position).cleared();
+            self.store_slot(position, slot);
+            if slot.state.is_vacant() {
+                self.release(I::from_usize(position).unwrap());
+            }
+        }
+        self.valid_slots = self.states.len();
+    }
+
+    /// Add a vacant slot to the free list, or retire it if the bag never reuses slots.
+    fn release(&mut self, index: I) {
+        if !self.free_indexes.push(index, &mut self.states) {
+            self.states[index.into_usize()] = SlotState::Retired;
+        }
+    }
+
+    /// The slot at a position as it is stored, ignoring any changes deferred by
+    /// [`IndexBag::reset`].
+    fn stored_slot(&self, position: usize) -> Slot<G> {
+        Slot {
+            state: self.states[position],
+            generation: self.generations[position],
+        }
+    }
+
+    fn store_slot(&mut self, position: usize, slot: Slot<G>) {
+        self.states[position] = slot.state;
+        self.generations[position] = slot.generation;
+    }
+
+    /// Vacate the slot at a position, returning whether it can be reused.
+    fn vacate_slot(&mut self, position: usize) -> bool {
+        let mut slot = self.stored_slot(position);
+        let reusable = slot.vacate();
+        self.store_slot(position, slot);
+        reusable
+    }
+
+    /// The slot at a position as it currently stands, including any changes deferred by
+    /// [`IndexBag::reset`].
+    fn current_slot(&self, position: usize) -> Option<Slot<G>> {
+        if position >= self.states.len() {
+            return None;
+        }
+        let mut slot = self.stored_slot(position);
+        if position >= self.valid_slots {
+            slot = slot.cleared();
+            if slot.state.is_vacant() && self.free_policy() == FreePolicy::NeverReuse {
+                slot.state = SlotState::Retired;
+            }
+        }
+        Some(slot)
+    }
+
+    /// Remove every item from the bag, keeping the allocated storage.
+    ///
+    /// Every index into the bag is invalidated, including those for slots reused by later
+    /// insertions, and indexes reserved with [`IndexBag::reserve_index`] are released. Every slot
+    /// is updated, which takes time in proportion to the number of slots; [`IndexBag::reset`]
+    /// defers the updates until the slots are reused.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::with_capacity(10);
+    /// let first = bag.insert(12);
+    /// let second = bag.insert(13);
+    /// bag.clear();
+    /// assert!(bag.is_empty());
+    /// assert_eq!(bag.unused_indexes(), 2);
+    /// assert!(bag.capacity() >= 10);
+    ///
+    /// let third = bag.insert(14);
+    /// assert_eq!(bag.get(first), None);
+    /// assert_eq!(bag.get(second), None);
+    /// assert_eq!(bag.get(third), Some(&14));
+    /// ```
+    pub fn clear(&mut self) {
+        self.reset();
+        self.update_cleared_slots();
+    }
+
+    /// Remove every item from the bag, invalidating every index into it without visiting the
+    /// slots.
+    ///
+    /// This behaves like [`IndexBag::clear`], but only the items are dropped straight away. Each
+    /// slot is updated when it is next reused, so resetting the bag takes constant time for
+    /// items that need no drop.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let first = bag.insert(12);
+    /// bag.reset();
+    /// assert_eq!(bag.get(first), None);
+    ///
+    /// let second = bag.insert(13);
+    /// assert_eq!(usize::from(second), usize::from(first));
+    /// assert_eq!(bag.get(first), None);
+    /// assert_eq!(bag.get(second), Some(&13));
+    /// ```
+    pub fn reset(&mut self) {
+        self.keys.clear();
+        self.free_indexes.clear();
+        self.valid_slots = 0;
+        self.values.clear();
+    }
+
+    /// Move the items in the bag to the first slots and discard the rest.
+    ///
+    /// Every index into the bag is invalidated, including those for items that keep their slot,
+    /// and the closure is called with the old and new index of each item so that references to
+    /// it can be updated. Items already in the first slots stay where they are, and the others
+    /// fill the gaps in no particular order. Indexes reserved with [`IndexBag::reserve_index`] are
+    /// released. The order of iteration is unchanged.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let indexes: Vec<_> = (0..4).map(|i| bag.insert(i)).collect();
+    /// bag.remove(indexes[0]);
+    /// bag.remove(indexes[2]);
+    ///
+    /// let mut moves = Vec::new();
+    /// bag.compact(|old, new| moves.push((old, new)));
+    /// assert_eq!(bag.pool_size(), 2);
+    /// assert_eq!(bag.unused_indexes(), 0);
+    /// for (old, new) in moves {
+    ///     assert_eq!(bag.get(old), None);
+    ///     assert!(usize::from(new) < 2);
+    /// }
+    /// assert_eq!(bag.values().collect::<Vec<_>>(), vec![&3, &1]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the generations of too many slots are exhausted for the items to be moved into
+    /// slots that can be referred to by the [`SlotIndex`] type. The bag is left unchanged.
+    pub fn compact<F>(&mut self, f: F)
+    where
+        F: FnMut(Index<T, G, I>, Index<T, G, I>),
+    {
+        self.compact_with(false, f)
+    }
+
+    /// Move the items in the bag to the first slots, keeping the order of their slots.
+    ///
+    /// This behaves like [`IndexBag::compact`], except that every item is given a slot before
+    /// that of any item that was in a later slot.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let indexes: Vec<_> = (0..4).map(|i| bag.insert(i)).collect();
+    /// bag.remove(indexes[0]);
+    /// bag.remove(indexes[2]);
+    ///
+    /// let mut moves = Vec::new();
+    /// bag.compact_ordered(|old, new| moves.push((usize::from(old), usize::from(new))));
+    /// moves.sort();
+    /// assert_eq!(moves, vec![(1, 0), (3, 1)]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`IndexBag::compact`].
+    pub fn compact_ordered<F>(&mut self, f: F)
+    where
+        F: FnMut(Index<T, G, I>, Index<T, G, I>),
+    {
+        self.compact_with(true, f)
+    }
+
+    fn compact_with<F>(&mut self, ordered: bool, mut f: F)
+    where
+        F: FnMut(Index<T, G, I>, Index<T, G, I>),
+    {
+        self.update_cleared_slots();
+
+        // Slots whose generation is exhausted are retired rather than reused, so the items may
+        // need slots past the end of the bag.
+        let usable = (0..self.states.len())
+            .map(|position| self.stored_slot(position))
+            .filter(|slot| match slot.state {
+                SlotState::Occupied(_) | SlotState::Reserved => slot.generation.next().is_some(),
+                _ => slot.reclaimable(),
+            })
+            .count();
+        let extra = self.len().saturating_sub(usable);
+        if extra > 0 && I::from_usize(self.states.len() + extra - 1).is_none() {
+            panic!("IndexBag has no more slots available to its index type");
+        }
+
+        // Vacating every slot invalidates every index into the bag. Slots retired because the bag
+        // never reuses slots can take the items too.
+        for position in 0..self.states.len() {
+            match self.states[position] {
+                SlotState::Occupied(_) | SlotState::Reserved => {
+                    self.vacate_slot(position);
+                }
+                SlotState::Retired if self.stored_slot(position).reclaimable() => {
+                    self.states[position] = SlotState::Vacant { next_free: NO_SLOT };
+                }
+                _ => {}
+            }
+        }
+        for _ in 0..extra {
+            self.states.push(SlotState::Vacant { next_free: NO_SLOT });
+            self.generations.push(self.first_generation);
+        }
+
+        let targets: Vec<usize> = self.states.iter()
+            .enumerate()
+            .filter(|&(_, state)| state.is_vacant())
+            .map(|(position, _)| position)
+            .take(self.len())
+            .collect();
+        let old_keys = self.keys.clone();
+        let mut assigned = Vec::new();
+        if ordered {
+            let mut order: Vec<usize> = (0..self.len()).collect();
+            order.sort_unstable_by_key(|&position| old_keys[position].slot());
+            assigned.resize(self.len(), 0);
+            for (&position, &target) in order.iter().zip(&targets) {
+                assigned[position] = target;
+            }
+        } else {
+            // Items whose slot is one of the targets keep it, and the rest fill the gaps.
+            let mut kept = Vec::new();
+            kept.resize(self.states.len(), false);
+            for key in &old_keys {
+                if targets.binary_search(&key.slot()).is_ok() {
+                    kept[key.slot()] = true;
+                }
+            }
+            let mut spare = targets.iter().filter(|&&target| !kept[target]);
+            for key in &old_keys {
+                let target = if kept[key.slot()] { key.slot() } else { *spare.next().unwrap() };
+                assigned.push(target);
+            }
+        }
+
+        for (position, target) in assigned.into_iter().enumerate() {
+            self.states[target] = SlotState::Occupied(position);
+            self.keys[position] = Index::new(I::from_usize(target).unwrap(), self.generations[target]);
+        }
+
+        self.truncate_reclaimable_slots();
+        self.free_indexes.clear();
+        for position in (0..self.states.len()).rev() {
+            if self.states[position].is_vacant() {
+                self.release(I::from_usize(position).unwrap());
+            }
+        }
+
+        for (&old, &new) in old_keys.iter().zip(&self.keys) {
+            f(old, new);
         }
     }
 
@@ -58,71 +628,689 @@ impl<T: ::core::fmt::Debug> IndexBag<T> {
     ///
     /// The bag expands only when it has no available unused indexes.
     pub fn pool_size(&self) -> usize {
-        self.data.len()
+        self.states.len()
     }
 
     /// The number of allocated but unused indexes in the bag.
+    ///
+    /// Slots emptied by [`IndexBag::reset`] are counted until they are reused, even if their
+    /// generation is exhausted.
     pub fn unused_indexes(&self) -> usize {
-        self.free_indexes.len()
+        self.free_indexes.len() + self.cleared_slots()
+    }
+
+    /// The number of slots emptied by [`IndexBag::reset`] that may still be reused.
+    fn cleared_slots(&self) -> usize {
+        match self.free_policy() {
+            FreePolicy::NeverReuse => 0,
+            _ => self.states.len() - self.valid_slots,
+        }
     }
 
     /// Insert an item into the bag.
-    pub fn insert(&mut self, value: T) -> Index {
-        if let Some(index) = self.free_indexes.pop() {
-            self.data[index].0 = Some(value);
-            self.data[index].1 += 1;
-            Index::new(index, self.data[index].1)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bag needs a new slot and every slot that can be referred to by the
+    /// [`SlotIndex`] type is already in use.
+    pub fn insert(&mut self, value: T) -> Index<T, G, I> {
+        self.insert_with(|_| value)
+    }
+
+    /// Insert an item constructed from the index it will be stored at.
+    ///
+    /// ```rust
+    /// use index_bag::{IndexBag, Index};
+    ///
+    /// struct Node {
+    ///     this: Index<Node>,
+    ///     next: Option<Index<Node>>,
+    /// }
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let first = bag.insert_with(|this| Node { this, next: None });
+    /// let second = bag.insert_with(|this| Node { this, next: Some(first) });
+    /// assert_eq!(bag.get(first).unwrap().this, first);
+    /// assert_eq!(bag.get(second).unwrap().next, Some(first));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bag needs a new slot and every slot that can be referred to by the
+    /// [`SlotIndex`] type is already in use. If the closure panics the bag is left unchanged.
+    pub fn insert_with<F>(&mut self, f: F) -> Index<T, G, I>
+    where
+        F: FnOnce(Index<T, G, I>) -> T,
+    {
+        let index = self.next_index()
+            .expect("IndexBag has no more slots available to its index type");
+        let value = f(index);
+        self.occupy(index, value);
+        index
+    }
+
+    /// Insert an item into the bag, returning it in an error if there is no space for it.
+    ///
+    /// Unlike [`IndexBag::insert`], this does not panic or abort if memory for the item can not
+    /// be allocated or the bag has run out of slots.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag: IndexBag<u32, u32, u8> = IndexBag::default();
+    /// for i in 0..256 {
+    ///     assert!(bag.try_insert(i).is_ok());
+    /// }
+    /// let error = bag.try_insert(256).unwrap_err();
+    /// assert_eq!(error.into_inner(), 256);
+    /// ```
+    pub fn try_insert(&mut self, value: T) -> Result<Index<T, G, I>, CapacityError<T>> {
+        let index = match self.next_index() {
+            Some(index) => index,
+            None => return Err(CapacityError::new(value)),
+        };
+        let new_slot = if index.slot() == self.states.len() { 1 } else { 0 };
+        let reserved = self.values.try_reserve(1)
+            .and_then(|()| self.keys.try_reserve(1))
+            .and_then(|()| self.generations.try_reserve(new_slot))
+            .and_then(|()| self.states.try_reserve(new_slot));
+        if reserved.is_err() {
+            return Err(CapacityError::new(value));
+        }
+        self.occupy(index, value);
+        Ok(index)
+    }
+
+    /// The index that the next item inserted into the bag will be stored at.
+    fn next_index(&self) -> Option<Index<T, G, I>> {
+        if let Some(index) = self.free_indexes.peek() {
+            return Some(Index::new(index, self.generations[index.into_usize()]));
+        }
+        let cleared = self.states.len() - self.cleared_slots();
+        let (position, generation) = (cleared..self.states.len())
+            .map(|position| (position, self.stored_slot(position).cleared()))
+            .find(|&(_, slot)| slot.state.is_vacant())
+            .map_or((self.states.len(), self.first_generation), |(position, slot)| {
+                (position, slot.generation)
+            });
+        Some(Index::new(I::from_usize(position)?, generation))
+    }
+
+    /// Store an item at the index returned by [`IndexBag::next_index`].
+    fn occupy(&mut self, index: Index<T, G, I>, value: T) {
+        let state = SlotState::Occupied(self.values.len());
+        self.claim(index, state);
+        self.values.push(value);
+        self.keys.push(index);
+    }
+
+    /// Take the slot at the index returned by [`IndexBag::next_index`] out of the free slots.
+    fn claim(&mut self, index: Index<T, G, I>, state: SlotState) {
+        let position = index.slot();
+        if position < self.valid_slots {
+            self.free_indexes.pop(&self.states);
+            self.states[position] = state;
+            return;
+        }
+
+        // Any slots emptied by `reset` that were passed over can not be reused.
+        let end = position.min(self.states.len());
+        for passed in self.valid_slots..end {
+            let generation = self.stored_slot(passed).cleared().generation;
+            self.store_slot(passed, Slot {
+                state: SlotState::Retired,
+                generation,
+            });
+        }
+        if position == self.states.len() {
+            self.states.push(state);
+            self.generations.push(index.generation());
         } else {
-            let index = Index::new(self.data.len(), 0);
-            self.data.push((Some(value), 0));
-            index
+            self.store_slot(position, Slot {
+                state,
+                generation: index.generation(),
+            });
+        }
+        self.valid_slots = position + 1;
+    }
+
+    /// Get an entry for the slot that the next item inserted into the bag will be stored at.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bag needs a new slot and every slot that can be referred to by the
+    /// [`SlotIndex`] type is already in use.
+    pub fn vacant_entry(&mut self) -> VacantEntry<'_, T, G, I> {
+        let index = self.next_index()
+            .expect("IndexBag has no more slots available to its index type");
+        VacantEntry::new(self, Some(index), false)
+    }
+
+    /// Get the entry for an index, for in-place updates and insertions.
+    ///
+    /// If the index refers to a slot that was reserved with [`IndexBag::reserve_index`], inserting
+    /// into the vacant entry fills the reservation. Otherwise the slot for a new item is only
+    /// chosen once the vacant entry is used, so looking up an entry never panics.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag: IndexBag<u32, u32, u8> = IndexBag::default();
+    /// let first = bag.insert(0);
+    /// bag.remove(first);
+    /// for i in 0..256 {
+    ///     bag.insert(i);
+    /// }
+    ///
+    /// // The bag is full, but the stale index can still be looked up.
+    /// bag.entry(first).and_modify(|value| *value += 1);
+    /// assert_eq!(bag.len(), 256);
+    /// ```
+    pub fn entry(&mut self, index: Index<T, G, I>) -> Entry<'_, T, G, I> {
+        match self.current_slot(index.slot()) {
+            Some(Slot { state: SlotState::Occupied(position), generation })
+                if generation == index.generation() =>
+            {
+                Entry::Occupied(OccupiedEntry::new(self, index, position))
+            }
+            Some(Slot { state: SlotState::Reserved, generation })
+                if generation == index.generation() =>
+            {
+                Entry::Vacant(VacantEntry::new(self, Some(index), true))
+            }
+            Some(slot) if slot.reused_since(index.generation()) => {
+                let current = match slot.state {
+                    SlotState::Occupied(_) | SlotState::Reserved => {
+                        Some(Index::new(index.index, slot.generation))
+                    }
+                    _ => None,
+                };
+                Entry::Stale(StaleEntry::new(self, index, current))
+            }
+            _ => Entry::Vacant(VacantEntry::new(self, None, false)),
         }
     }
 
+    /// Reserve a slot for an item that will be provided later with [`IndexBag::fill`].
+    ///
+    /// The reserved index does not resolve to anything, and the slot is not reused or visited by
+    /// iteration, until it is filled.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let index = bag.reserve_index();
+    /// assert_eq!(bag.get(index), None);
+    /// assert_eq!(bag.len(), 0);
+    ///
+    /// let other = bag.insert(12);
+    /// assert_ne!(other, index);
+    ///
+    /// assert_eq!(bag.fill(index, 13), Ok(()));
+    /// assert_eq!(bag.get(index), Some(&13));
+    /// assert_eq!(bag.fill(index, 14), Err(14));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bag needs a new slot and every slot that can be referred to by the
+    /// [`SlotIndex`] type is already in use.
+    pub fn reserve_index(&mut self) -> Index<T, G, I> {
+        let index = self.next_index()
+            .expect("IndexBag has no more slots available to its index type");
+        self.claim(index, SlotState::Reserved);
+        index
+    }
+
+    /// Provide the item for a slot reserved with [`IndexBag::reserve_index`].
+    ///
+    /// The item is returned if the index does not refer to a reserved slot.
+    pub fn fill(&mut self, index: Index<T, G, I>, value: T) -> Result<(), T> {
+        match self.current_slot(index.slot()) {
+            Some(slot) if slot.state == SlotState::Reserved
+                && slot.generation == index.generation() =>
+            {
+                self.states[index.slot()] = SlotState::Occupied(self.values.len());
+            }
+            _ => return Err(value),
+        }
+        self.values.push(value);
+        self.keys.push(index);
+        Ok(())
+    }
+
     /// Remove an item from the bag.
-    pub fn remove(&mut self, index: Index) -> Option<T> {
-        if let Some((ref mut value @ Some(_), generation)) = self.data.get_mut(index.index) {
-            if *generation == index.generation {
-                let mut inner = None;
-                swap(&mut inner, value);
-                self.free_indexes.push(index.index);
-                inner
+    ///
+    /// The generation of the slot that held the item is advanced so that the index will not
+    /// resolve to any later item stored in the slot. If the generation is exhausted the slot is
+    /// retired and never reused.
+    pub fn remove(&mut self, index: Index<T, G, I>) -> Option<T> {
+        self.try_remove(index).ok()
+    }
+
+    /// Remove an item from the bag, describing why the index does not refer to an item if it
+    /// can not be removed.
+    pub fn try_remove(&mut self, index: Index<T, G, I>) -> Result<T, LookupError<G>> {
+        let position = self.lookup(index)?;
+        Ok(self.remove_at(position).1)
+    }
+
+    /// Remove the item at the given position in the dense storage.
+    ///
+    /// The last item takes its place, so the bag is left consistent after every removal.
+    fn remove_at(&mut self, position: usize) -> (Index<T, G, I>, T) {
+        let index = self.keys.swap_remove(position);
+        if self.vacate_slot(index.slot()) {
+            self.release(index.index);
+        }
+
+        let value = self.values.swap_remove(position);
+        if let Some(moved) = self.keys.get(position) {
+            self.states[moved.slot()] = SlotState::Occupied(position);
+        }
+        (index, value)
+    }
+
+    /// Keep only the items for which the predicate returns `true`.
+    ///
+    /// The predicate is called once for each item, with its index. Each item is removed as soon
+    /// as the predicate rejects it, so if the predicate panics the bag holds every item that has
+    /// not been rejected yet.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let indexes: Vec<_> = (0..6).map(|i| bag.insert(i)).collect();
+    /// bag.retain(|_, &value| value % 2 == 0);
+    /// assert_eq!(bag.len(), 3);
+    /// assert_eq!(bag.get(indexes[1]), None);
+    /// assert_eq!(bag.get(indexes[4]), Some(&4));
+    /// ```
+    ///
+    /// ```rust
+    /// use std::panic::{catch_unwind, AssertUnwindSafe};
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let first = bag.insert(1);
+    /// let second = bag.insert(2);
+    /// let result = catch_unwind(AssertUnwindSafe(|| {
+    ///     bag.retain(|_, &value| if value == 1 { false } else { panic!() });
+    /// }));
+    /// assert!(result.is_err());
+    /// assert_eq!(bag.get(first), None);
+    /// assert_eq!(bag.get(second), Some(&2));
+    /// assert_eq!(bag.unused_indexes(), 1);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Index<T, G, I>, &T) -> bool,
+    {
+        self.retain_mut(|index, value| f(index, value))
+    }
+
+    /// Keep only the items for which the predicate returns `true`, allowing the predicate to
+    /// modify the items.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let first = bag.insert(1);
+    /// let second = bag.insert(2);
+    /// bag.retain_mut(|_, value| {
+    ///     *value -= 1;
+    ///     *value > 0
+    /// });
+    /// assert_eq!(bag.get(first), None);
+    /// assert_eq!(bag.get(second), Some(&1));
+    /// ```
+    pub fn retain_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Index<T, G, I>, &mut T) -> bool,
+    {
+        let mut position = 0;
+        while position < self.len() {
+            if f(self.keys[position], &mut self.values[position]) {
+                position += 1;
             } else {
-                None
+                self.remove_at(position);
             }
-        } else {
-            None
         }
     }
 
+    /// Remove the items for which the predicate returns `true`, iterating over the removed
+    /// indexes and items.
+    ///
+    /// Items are only visited as the iterator is advanced, and any items that have not been
+    /// visited when the iterator is dropped are kept.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let small = bag.insert(1);
+    /// let large = bag.insert(100);
+    /// let removed: Vec<_> = bag.extract_if(|_, value| *value > 10).collect();
+    /// assert_eq!(removed, vec![(large, 100)]);
+    /// assert_eq!(bag.get(small), Some(&1));
+    /// assert_eq!(bag.get(large), None);
+    /// ```
+    pub fn extract_if<F>(&mut self, f: F) -> ExtractIf<'_, T, F, G, I>
+    where
+        F: FnMut(Index<T, G, I>, &mut T) -> bool,
+    {
+        ExtractIf::new(self, f)
+    }
+
+    /// Check whether an index refers to an item in the bag.
+    ///
+    /// Stale indexes are rejected by the generation of their slot alone, and the state of the slot
+    /// is only read for an index whose generation matches.
+    ///
+    /// ```rust
+    /// use index_bag::{IndexBag, Index};
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let index = bag.insert(12);
+    /// let reserved = bag.reserve_index();
+    /// assert!(bag.contains(index));
+    /// assert!(!bag.contains(reserved));
+    ///
+    /// bag.remove(index);
+    /// assert!(!bag.contains(index));
+    ///
+    /// // An index made for the vacant slot refers to no item either.
+    /// let made = bag.get_index(index.slot()).unwrap();
+    /// let raw: Index<i32> = Index::from_raw_parts(made.slot(), made.generation()).unwrap();
+    /// assert!(!bag.contains(made));
+    /// assert!(!bag.contains(raw));
+    /// assert_eq!(bag.get(made), None);
+    /// ```
+    pub fn contains(&self, index: Index<T, G, I>) -> bool {
+        self.is_valid(index) && matches!(self.states[index.slot()], SlotState::Occupied(_))
+    }
+
+    /// Check whether an index may still refer to its slot, reading only the generation of the
+    /// slot.
+    ///
+    /// This is the cheapest way to find out whether an index has been invalidated, but only a
+    /// `false` result is conclusive. An index for which this returns `true` refers to an item, to
+    /// a slot reserved with [`IndexBag::reserve_index`], or to a vacant slot if it was made for
+    /// that slot, for instance by [`IndexBag::get_index`] or [`Index::from_raw_parts`]. Use
+    /// [`IndexBag::contains`] to find out whether it refers to an item.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let index = bag.insert(12);
+    /// let reserved = bag.reserve_index();
+    /// assert!(bag.is_valid(index));
+    /// assert!(bag.is_valid(reserved));
+    ///
+    /// bag.remove(index);
+    /// assert!(!bag.is_valid(index));
+    ///
+    /// // An index made for the vacant slot passes, although it refers to no item.
+    /// let made = bag.get_index(index.slot()).unwrap();
+    /// assert!(bag.is_valid(made));
+    /// assert!(!bag.contains(made));
+    ///
+    /// bag.reset();
+    /// assert!(!bag.is_valid(made));
+    /// assert!(!bag.is_valid(reserved));
+    /// ```
+    pub fn is_valid(&self, index: Index<T, G, I>) -> bool {
+        let position = index.slot();
+        // Slots emptied by `reset` keep their old generation until they are reused.
+        position < self.valid_slots && self.generations[position] == index.generation()
+    }
+
     /// Get a reference to an item in the bag.
-    pub fn get(&self, index: Index) -> Option<&T> {
-        self.data.get(index.index)
-            .and_then(|(value, generation)| {
-                if *generation == index.generation {
-                    value.as_ref()
-                } else {
-                    None
-                }
-            })
+    pub fn get(&self, index: Index<T, G, I>) -> Option<&T> {
+        self.try_get(index).ok()
+    }
+
+    /// Get a reference to an item in the bag, describing why the index does not refer to an item
+    /// if it can not be found.
+    pub fn try_get(&self, index: Index<T, G, I>) -> Result<&T, LookupError<G>> {
+        self.lookup(index)
+            .map(|position| &self.values[position])
     }
 
     /// Get a mutable reference to an item in the bag.
-    pub fn get_mut(&mut self, index: Index) -> Option<&mut T> {
-        self.data.get_mut(index.index)
-            .and_then(|(value, generation)| {
-                if *generation == index.generation {
-                    value.as_mut()
-                } else {
-                    None
-                }
-            })
+    pub fn get_mut(&mut self, index: Index<T, G, I>) -> Option<&mut T> {
+        self.try_get_mut(index).ok()
+    }
+
+    /// Get a mutable reference to an item in the bag, describing why the index does not refer to
+    /// an item if it can not be found.
+    pub fn try_get_mut(&mut self, index: Index<T, G, I>) -> Result<&mut T, LookupError<G>> {
+        self.lookup(index)
+            .map(move |position| &mut self.values[position])
+    }
+
+    /// Get mutable references to two different items in the bag at once.
+    ///
+    /// Returns `None` if either index does not refer to an item, or both refer to the same item.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let first = bag.insert(12);
+    /// let second = bag.insert(13);
+    /// if let Some((first, second)) = bag.get2_mut(first, second) {
+    ///     ::std::mem::swap(first, second);
+    /// }
+    /// assert_eq!(bag[first], 13);
+    /// assert_eq!(bag[second], 12);
+    /// assert_eq!(bag.get2_mut(first, first), None);
+    /// ```
+    pub fn get2_mut(&mut self, first: Index<T, G, I>, second: Index<T, G, I>)
+        -> Option<(&mut T, &mut T)>
+    {
+        self.get_many_mut([first, second])
+            .map(|[first, second]| (first, second))
+    }
+
+    /// Get mutable references to several different items in the bag at once.
+    ///
+    /// Returns `None` if any index does not refer to an item, or any two refer to the same item.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let indexes = [bag.insert(1), bag.insert(2), bag.insert(3)];
+    /// for value in bag.get_many_mut(indexes).unwrap().iter_mut() {
+    ///     **value *= 10;
+    /// }
+    /// assert_eq!(bag.values().collect::<Vec<_>>(), vec![&10, &20, &30]);
+    /// assert_eq!(bag.get_many_mut([indexes[0], indexes[2], indexes[0]]), None);
+    /// ```
+    pub fn get_many_mut<const N: usize>(&mut self, indexes: [Index<T, G, I>; N])
+        -> Option<[&mut T; N]>
+    {
+        let mut positions = [0; N];
+        for (i, &index) in indexes.iter().enumerate() {
+            let position = self.lookup(index).ok()?;
+            if positions[..i].contains(&position) {
+                return None;
+            }
+            positions[i] = position;
+        }
+        // The positions were all found to be occupied and distinct.
+        Some(unsafe { self.values_at(positions) })
+    }
+
+    /// Get mutable references to several different items in the bag at once, without checking
+    /// the indexes.
+    ///
+    /// This is [`IndexBag::get_many_mut`] for callers that already know the indexes are valid and
+    /// distinct, for instance because the items were just inserted.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let from = bag.insert(vec![1, 2, 3]);
+    /// let to = bag.insert(vec![]);
+    ///
+    /// // Safety: both indexes were just returned by `insert`, so they refer to distinct items.
+    /// let [from, to] = unsafe { bag.get_many_unchecked_mut([from, to]) };
+    /// to.append(from);
+    /// assert!(from.is_empty());
+    /// assert_eq!(*to, vec![1, 2, 3]);
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// Every index must refer to an item in the bag, and no two indexes may refer to the same
+    /// item. Otherwise the behaviour is undefined.
+    pub unsafe fn get_many_unchecked_mut<const N: usize>(&mut self, indexes: [Index<T, G, I>; N])
+        -> [&mut T; N]
+    {
+        let states = &self.states;
+        let positions = indexes.map(|index| {
+            match unsafe { *states.get_unchecked(index.slot()) } {
+                SlotState::Occupied(position) => position,
+                _ => unsafe { hint::unreachable_unchecked() },
+            }
+        });
+        unsafe { self.values_at(positions) }
+    }
+
+    /// Get mutable references to the items at several positions in the dense storage.
+    ///
+    /// # Safety
+    ///
+    /// Every position must be in bounds and no two positions may be the same.
+    unsafe fn values_at<const N: usize>(&mut self, positions: [usize; N]) -> [&mut T; N] {
+        let values = self.values.as_mut_ptr();
+        positions.map(|position| unsafe { &mut *values.add(position) })
+    }
+
+    /// Find the position of the item referred to by an index in the dense storage.
+    fn lookup(&self, index: Index<T, G, I>) -> Result<usize, LookupError<G>> {
+        match self.current_slot(index.slot()) {
+            Some(Slot { state: SlotState::Occupied(position), generation })
+                if generation == index.generation() =>
+            {
+                Ok(position)
+            }
+            Some(slot) if slot.reused_since(index.generation()) => {
+                Err(LookupError::Stale { generation: slot.generation })
+            }
+            Some(Slot { generation, .. }) => Err(LookupError::Vacant { generation }),
+            None => Err(LookupError::OutOfRange),
+        }
+    }
+
+    /// Iterate over the indexes and items in the bag.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let first = bag.insert(12);
+    /// let second = bag.insert(13);
+    /// let third = bag.insert(14);
+    /// bag.remove(second);
+    ///
+    /// let mut iter = bag.iter();
+    /// assert_eq!(iter.next(), Some((first, &12)));
+    /// assert_eq!(iter.next(), Some((third, &14)));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T, G, I> {
+        Iter::new(self)
+    }
+
+    /// Iterate over the indexes and mutable references to the items in the bag.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let index = bag.insert(12);
+    /// for (_, value) in bag.iter_mut() {
+    ///     *value += 1;
+    /// }
+    /// assert_eq!(bag.get(index), Some(&13));
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, G, I> {
+        IterMut::new(self)
+    }
+
+    /// Remove all of the items from the bag, iterating over the removed indexes and items.
+    ///
+    /// Any items not consumed from the iterator are still removed. Every index into the bag is
+    /// invalidated, including those for slots reused by later insertions.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let first = bag.insert(12);
+    /// let second = bag.insert(13);
+    /// assert_eq!(bag.drain().collect::<Vec<_>>(), vec![(first, 12), (second, 13)]);
+    /// assert_eq!(bag.get(first), None);
+    ///
+    /// let third = bag.insert(14);
+    /// assert_eq!(bag.get(first), None);
+    /// assert_eq!(bag.get(second), None);
+    /// assert_eq!(bag.get(third), Some(&14));
+    /// ```
+    pub fn drain(&mut self) -> Drain<'_, T, G, I> {
+        Drain::new(self)
+    }
+
+    /// Iterate over the indexes of the items in the bag.
+    pub fn keys(&self) -> Keys<'_, T, G, I> {
+        Keys::new(self)
+    }
+
+    /// Iterate over the items in the bag.
+    pub fn values(&self) -> Values<'_, T> {
+        Values::new(self)
+    }
+
+    /// Iterate over mutable references to the items in the bag.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, T> {
+        ValuesMut::new(self)
+    }
+
+    /// A table from the index of each item to its index once the bag is saved with
+    /// `compact::serialize` and read back with `compact::deserialize`.
+    ///
+    /// The `compact` module, enabled by the `serde` feature, saves a bag without its vacant slots.
+    /// The table does not describe [`IndexBag::compact`] or [`IndexBag::compact_ordered`], which
+    /// move the items in place and report their new indexes to a callback instead.
+    pub fn compact_remap(&self) -> Remap<T, G, I> {
+        Remap::new(self)
+    }
+
+    /// Brand the bag for the duration of a closure.
+    ///
+    /// The [`BrandedBag`] passed to the closure only accepts indexes that it created itself, so an
+    /// index from one bag can not be used to access items in another, even when both bags hold
+    /// items of the same type.
+    pub fn branded<'a, R, F>(&'a mut self, f: F) -> R
+    where
+        F: for<'id> FnOnce(BrandedBag<'id, 'a, T, G, I>) -> R,
+    {
+        f(BrandedBag::new(self))
     }
 
     /// Translate a [`usize`] index to an [`Index`].
     ///
     /// The generated [`Index`] will refer to the item in the bag that currently resides at a given
-    /// numeric index.
+    /// numeric index. An index is returned even if the slot holds no item, and it will refer to the
+    /// next item stored there; [`IndexBag::upgrade`] only returns indexes to items.
     ///
     /// ```rust
     /// use index_bag::IndexBag;
@@ -140,30 +1328,259 @@ impl<T: ::core::fmt::Debug> IndexBag<T> {
     /// assert_eq!(new_index, current_index);
     /// assert_eq!(bag.remove(current_index), Some(13));
     /// ```
-    pub fn get_index(&self, index: usize) -> Option<Index> {
-        self.data.get(index)
-            .map(|(_, generation)| Index::new(index, *generation))
+    pub fn get_index(&self, index: usize) -> Option<Index<T, G, I>> {
+        let slot = self.current_slot(index)?;
+        // Slots whose generation is exhausted have no index.
+        slot.generation.try_into_non_zero()?;
+        Some(Index::new(I::from_usize(index)?, slot.generation))
+    }
+
+    /// Translate a [`usize`] index to the [`Index`] of the item stored at it, or `None` if the slot
+    /// holds no item.
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    /// let mut bag = IndexBag::new();
+    ///
+    /// let index = bag.insert(12);
+    /// let i_index: usize = index.into();
+    /// assert_eq!(bag.upgrade(i_index), Some(index));
+    ///
+    /// bag.remove(index);
+    /// assert_eq!(bag.upgrade(i_index), None);
+    /// assert_eq!(bag.upgrade(i_index + 1), None);
+    /// ```
+    pub fn upgrade(&self, index: usize) -> Option<Index<T, G, I>> {
+        match self.current_slot(index)? {
+            Slot { state: SlotState::Occupied(position), .. } => Some(self.keys[position]),
+            _ => None,
+        }
+    }
+
+    /// The current generation of the slot at a [`usize`] index, or `None` if the index is past
+    /// the end of the bag.
+    ///
+    /// The generation of a slot advances each time an item is removed from it. A slot whose
+    /// generation is exhausted reports [`Generation::exhausted`].
+    ///
+    /// ```rust
+    /// use index_bag::IndexBag;
+    /// let mut bag = IndexBag::new();
+    ///
+    /// let index = bag.insert(12);
+    /// assert_eq!(bag.generation_of(index.slot()), Some(index.generation()));
+    ///
+    /// bag.remove(index);
+    /// assert_eq!(bag.generation_of(index.slot()), Some(index.generation() + 1));
+    /// assert_eq!(bag.generation_of(index.slot() + 1), None);
+    /// ```
+    pub fn generation_of(&self, index: usize) -> Option<G> {
+        self.current_slot(index).map(|slot| slot.generation)
+    }
+}
+
+impl<T: fmt::Debug, G: Generation, I: SlotIndex> fmt::Debug for IndexBag<T, G, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter())
+            .finish()
+    }
+}
+
+/// Access an item in the bag, panicking if the index does not refer to an item.
+///
+/// ```rust
+/// use index_bag::IndexBag;
+///
+/// let mut bag = IndexBag::new();
+/// let index = bag.insert(12);
+/// bag[index] += 1;
+/// assert_eq!(bag[index], 13);
+/// ```
+///
+/// The panic message describes why the index could not be resolved.
+///
+/// ```rust,should_panic(expected = "is stale")
+/// use index_bag::IndexBag;
+///
+/// let mut bag = IndexBag::new();
+/// let index = bag.insert(12);
+/// bag.remove(index);
+/// bag.insert(13);
+/// bag[index];
+/// ```
+impl<T, G: Generation, I: SlotIndex> ops::Index<Index<T, G, I>>
+    for IndexBag<T, G, I>
+{
+    type Output = T;
+
+    fn index(&self, index: Index<T, G, I>) -> &T {
+        match self.try_get(index) {
+            Ok(value) => value,
+            Err(error) => panic!("{:?} can not be used to access the bag: {}", index, error),
+        }
+    }
+}
+
+impl<T, G: Generation, I: SlotIndex> ops::IndexMut<Index<T, G, I>>
+    for IndexBag<T, G, I>
+{
+    fn index_mut(&mut self, index: Index<T, G, I>) -> &mut T {
+        match self.try_get_mut(index) {
+            Ok(value) => value,
+            Err(error) => panic!("{:?} can not be used to access the bag: {}", index, error),
+        }
     }
 }
 
 /// An index into an IndexBag.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Index {
-    index: usize,
-    generation: usize,
+///
+/// An [`Index`] is bound to the type of the items in the [`IndexBag`] that created it, so an
+/// index into a bag of one type cannot be used to look up an item in a bag of another.
+///
+/// The generation is stored as a non-zero integer, so an `Option<Index>` takes no more space than
+/// an [`Index`].
+///
+/// ```rust,compile_fail
+/// use index_bag::IndexBag;
+///
+/// let mut numbers = IndexBag::new();
+/// let mut names: IndexBag<&str> = IndexBag::new();
+/// let index = numbers.insert(12);
+/// names.get(index);
+/// ```
+pub struct Index<T, G: Generation = usize, I = usize> {
+    index: I,
+    generation: G::NonZero,
+    item: PhantomData<fn() -> T>,
 }
 
-impl Index {
-    fn new(index: usize, generation: usize) -> Index {
+// An `Option<Index>` uses the niche in the generation and is no larger than an `Index`.
+const _: () = assert!(mem::size_of::<Option<Index<()>>>() == mem::size_of::<Index<()>>());
+
+impl<T, G: Generation, I: SlotIndex> Index<T, G, I> {
+    fn new(index: I, generation: G) -> Index<T, G, I> {
         Index {
             index,
-            generation,
+            generation: generation.into_non_zero(),
+            item: PhantomData,
+        }
+    }
+
+    /// Convert the index into an index for a bag of another type.
+    ///
+    /// The converted index refers to the same position and generation as the original, so it
+    /// will resolve in any bag where the original would have resolved. This is only useful where
+    /// bags of different types are kept in step with each other (such as storing the components
+    /// of one entity at the same position in several bags); otherwise the converted index will
+    /// refer to an unrelated item.
+    ///
+    /// ```rust
+    /// use index_bag::{IndexBag, Index};
+    ///
+    /// let mut positions = IndexBag::new();
+    /// let mut names = IndexBag::new();
+    /// let position = positions.insert((1, 2));
+    /// let name = names.insert("origin");
+    ///
+    /// let cast: Index<&str> = position.cast();
+    /// assert_eq!(cast, name);
+    /// assert_eq!(names.get(cast), Some(&"origin"));
+    /// ```
+    pub fn cast<U>(self) -> Index<U, G, I> {
+        Index::new(self.index, self.generation())
+    }
+
+    /// Create an index from the position of its slot and its generation, for instance to recover
+    /// an index passed through FFI as a pair of integers.
+    ///
+    /// Returns `None` if the position can not be referred to by the [`SlotIndex`] type or the
+    /// generation can never be given to an index.
+    ///
+    /// ```rust
+    /// use index_bag::{IndexBag, Index};
+    ///
+    /// let mut bag = IndexBag::new();
+    /// let index = bag.insert(12);
+    ///
+    /// let (slot, generation) = (index.slot(), index.generation());
+    /// let recovered: Index<i32> = Index::from_raw_parts(slot, generation).unwrap();
+    /// assert_eq!(recovered, index);
+    /// assert_eq!(bag.get(recovered), Some(&12));
+    ///
+    /// assert_eq!(Index::<i32, u8, u8>::from_raw_parts(256, 0), None);
+    /// assert_eq!(Index::<i32, u8, u8>::from_raw_parts(0, u8::MAX), None);
+    /// ```
+    pub fn from_raw_parts(slot: usize, generation: G) -> Option<Index<T, G, I>> {
+        Some(Index {
+            index: I::from_usize(slot)?,
+            generation: generation.try_into_non_zero()?,
+            item: PhantomData,
+        })
+    }
+
+    /// The position of the slot the index refers to.
+    pub fn slot(self) -> usize {
+        self.index.into_usize()
+    }
+
+    /// The generation of the slot the index refers to.
+    pub fn generation(self) -> G {
+        G::from_non_zero(self.generation)
+    }
+}
+
+impl<T, G: Generation, I: fmt::Debug> fmt::Debug for Index<T, G, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Index")
+            .field("index", &self.index)
+            .field("generation", &G::from_non_zero(self.generation))
+            .finish()
+    }
+}
+
+impl<T, G: Generation, I: Clone> Clone for Index<T, G, I> {
+    fn clone(&self) -> Index<T, G, I> {
+        Index {
+            index: self.index.clone(),
+            generation: self.generation,
+            item: PhantomData,
         }
     }
 }
 
-impl Into<usize> for Index {
-    fn into(self) -> usize {
-        self.index
+impl<T, G: Generation, I: Copy> Copy for Index<T, G, I> {}
+
+impl<T, G: Generation, I: PartialEq> PartialEq for Index<T, G, I> {
+    fn eq(&self, other: &Index<T, G, I>) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T, G: Generation, I: Eq> Eq for Index<T, G, I> {}
+
+impl<T, G: Generation, I: Ord> PartialOrd for Index<T, G, I> {
+    fn partial_cmp(&self, other: &Index<T, G, I>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, G: Generation, I: Ord> Ord for Index<T, G, I> {
+    fn cmp(&self, other: &Index<T, G, I>) -> Ordering {
+        self.index.cmp(&other.index)
+            .then_with(|| self.generation.cmp(&other.generation))
+    }
+}
+
+impl<T, G: Generation, I: Hash> Hash for Index<T, G, I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T, G: Generation, I: SlotIndex> From<Index<T, G, I>> for usize {
+    fn from(index: Index<T, G, I>) -> usize {
+        index.index.into_usize()
     }
 }
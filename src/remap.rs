@@ -0,0 +1,232 @@
+//! Rewriting indexes into a bag whose items have been moved to new slots.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::{Generation, Index, IndexBag, SlotIndex};
+
+/// A table from the indexes of the items in a bag to their indexes once the bag is saved with
+/// `compact::serialize` and read back with `compact::deserialize`.
+///
+/// Created by [`IndexBag::compact_remap`]. Items keep their order, but are moved to the first
+/// slots of the loaded bag so that it has no vacant slots. The table does not describe
+/// [`IndexBag::compact`] or [`IndexBag::compact_ordered`], which assign slots differently.
+///
+/// ```rust
+/// use index_bag::IndexBag;
+///
+/// let mut bag = IndexBag::new();
+/// let first = bag.insert(12);
+/// let second = bag.insert(13);
+/// bag.remove(first);
+///
+/// let remap = bag.compact_remap();
+/// assert_eq!(remap.get(first), None);
+/// assert_eq!(usize::from(remap.get(second).unwrap()), 0);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "G: ::serde::Serialize, I: ::serde::Serialize")))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "G: ::serde::Deserialize<'de>, I: ::serde::Deserialize<'de> + SlotIndex")))]
+pub struct Remap<T, G: Generation = usize, I = usize> {
+    /// The slot and generation of each item in the original bag, with the new slot of the item,
+    /// sorted by the original slot. Vacant slots take no space.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_entries"))]
+    entries: Vec<(I, G, I)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    item: PhantomData<fn() -> T>,
+}
+
+/// Read the entries of a [`Remap`], sorting them so they can be searched.
+#[cfg(feature = "serde")]
+fn deserialize_entries<'de, G, I, D>(deserializer: D) -> Result<Vec<(I, G, I)>, D::Error>
+where
+    G: Deserialize<'de>,
+    I: Deserialize<'de> + SlotIndex,
+    D: Deserializer<'de>,
+{
+    let mut entries = Vec::<(I, G, I)>::deserialize(deserializer)?;
+    entries.sort_unstable_by_key(|&(old, _, _)| old.into_usize());
+    Ok(entries)
+}
+
+/// The generation of every item in a bag read back by `compact::deserialize`.
+///
+/// New slots in such a bag start at this generation too, so an index with the first
+/// generation never resolves in it.
+pub(crate) fn compact_generation<G: Generation>() -> G {
+    G::first().next().expect("generation has no successor to its first value")
+}
+
+impl<T, G: Generation, I: SlotIndex> Remap<T, G, I> {
+    pub(crate) fn new(bag: &IndexBag<T, G, I>) -> Remap<T, G, I> {
+        let mut entries: Vec<(I, G, I)> = bag.keys.iter()
+            .enumerate()
+            .map(|(position, key)| {
+                let slot = I::from_usize(position).expect("bag has more items than slots");
+                (key.index, key.generation(), slot)
+            })
+            .collect();
+        entries.sort_unstable_by_key(|&(old, _, _)| old.into_usize());
+        Remap {
+            entries,
+            item: PhantomData,
+        }
+    }
+
+    /// The index of an item in the loaded bag, or `None` if the index did not refer to an item.
+    pub fn get(&self, index: Index<T, G, I>) -> Option<Index<T, G, I>> {
+        let found = self.entries.binary_search_by_key(&index.slot(), |&(old, _, _)| old.into_usize());
+        match found.map(|position| self.entries[position]) {
+            Ok((_, generation, slot)) if generation == index.generation() => {
+                Some(Index::new(slot, compact_generation()))
+            }
+            _ => None,
+        }
+    }
+
+    /// The index of an item in the loaded bag.
+    ///
+    /// An index that did not refer to an item is mapped to an index that never resolves in the
+    /// loaded bag.
+    pub fn index(&self, index: Index<T, G, I>) -> Index<T, G, I> {
+        self.get(index).unwrap_or_else(|| {
+            let slot = I::from_usize(0).expect("index type can not refer to the first slot");
+            Index::new(slot, G::first())
+        })
+    }
+}
+
+impl<T, G: Generation, I: Clone> Clone for Remap<T, G, I> {
+    fn clone(&self) -> Remap<T, G, I> {
+        Remap {
+            entries: self.entries.clone(),
+            item: PhantomData,
+        }
+    }
+}
+
+impl<T, G: Generation, I: fmt::Debug> fmt::Debug for Remap<T, G, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Remap")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+/// Data holding indexes that can be rewritten with a [`Remap`].
+///
+/// Implement this for types that store indexes into a bag that is saved with `compact::serialize`,
+/// by remapping each of their fields in turn. Once the bag is read back with
+/// `compact::deserialize`, the remap table saved with it is applied to the items and to any other
+/// data loaded alongside.
+///
+/// ```rust
+/// # #[cfg(feature = "serde")]
+/// # fn main() {
+/// extern crate serde;
+/// extern crate serde_json;
+/// use index_bag::{compact, IndexBag, Index, Remap, RemapIndexes};
+///
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Node {
+///     parent: Option<Index<Node>>,
+///     children: Vec<Index<Node>>,
+/// }
+///
+/// impl RemapIndexes<Node> for Node {
+///     fn remap_indexes(&mut self, remap: &Remap<Node>) {
+///         self.parent.remap_indexes(remap);
+///         self.children.remap_indexes(remap);
+///     }
+/// }
+///
+/// let mut bag = IndexBag::new();
+/// let removed = bag.insert(Node { parent: None, children: vec![] });
+/// let root = bag.insert(Node { parent: None, children: vec![] });
+/// let child = bag.insert(Node { parent: Some(removed), children: vec![] });
+/// bag[root].children.push(child);
+/// bag.remove(removed);
+///
+/// // Save the items, and the remap table along with the index of the root.
+/// let mut items = Vec::new();
+/// compact::serialize(&bag, &mut serde_json::Serializer::new(&mut items)).unwrap();
+/// let extra = serde_json::to_string(&(bag.compact_remap(), root)).unwrap();
+///
+/// // The loaded items and root still hold the indexes of the saved bag until they are remapped.
+/// let mut deserializer = serde_json::Deserializer::from_slice(&items);
+/// let mut bag: IndexBag<Node> = compact::deserialize(&mut deserializer).unwrap();
+/// let (remap, mut root): (Remap<Node>, Index<Node>) = serde_json::from_str(&extra).unwrap();
+/// bag.remap_indexes(&remap);
+/// root.remap_indexes(&remap);
+///
+/// let child = bag[root].children[0];
+/// assert_eq!(bag[child].parent, None);
+/// assert_eq!(bag.len(), 2);
+/// # }
+/// # #[cfg(not(feature = "serde"))]
+/// # fn main() {}
+/// ```
+pub trait RemapIndexes<T, G: Generation = usize, I = usize> {
+    /// Rewrite every index in the value to its index in the loaded bag.
+    fn remap_indexes(&mut self, remap: &Remap<T, G, I>);
+}
+
+impl<T, G: Generation, I: SlotIndex> RemapIndexes<T, G, I> for Index<T, G, I> {
+    /// Rewrite the index with [`Remap::index`].
+    fn remap_indexes(&mut self, remap: &Remap<T, G, I>) {
+        *self = remap.index(*self);
+    }
+}
+
+impl<T, G: Generation, I: SlotIndex> RemapIndexes<T, G, I> for Option<Index<T, G, I>> {
+    /// Rewrite the index with [`Remap::get`], clearing it if it did not refer to an item.
+    fn remap_indexes(&mut self, remap: &Remap<T, G, I>) {
+        *self = self.and_then(|index| remap.get(index));
+    }
+}
+
+impl<T, G: Generation, I, R: RemapIndexes<T, G, I>> RemapIndexes<T, G, I> for [R] {
+    fn remap_indexes(&mut self, remap: &Remap<T, G, I>) {
+        for value in self {
+            value.remap_indexes(remap);
+        }
+    }
+}
+
+impl<T, G: Generation, I, R: RemapIndexes<T, G, I>, const N: usize> RemapIndexes<T, G, I> for [R; N] {
+    fn remap_indexes(&mut self, remap: &Remap<T, G, I>) {
+        self[..].remap_indexes(remap);
+    }
+}
+
+impl<T, G: Generation, I, R: RemapIndexes<T, G, I>> RemapIndexes<T, G, I> for Vec<R> {
+    fn remap_indexes(&mut self, remap: &Remap<T, G, I>) {
+        self[..].remap_indexes(remap);
+    }
+}
+
+impl<T, G: Generation, I, R: RemapIndexes<T, G, I> + ?Sized> RemapIndexes<T, G, I> for Box<R> {
+    fn remap_indexes(&mut self, remap: &Remap<T, G, I>) {
+        (**self).remap_indexes(remap);
+    }
+}
+
+impl<T, G, I, U, H, J> RemapIndexes<T, G, I> for IndexBag<U, H, J>
+where
+    G: Generation,
+    H: Generation,
+    J: SlotIndex,
+    U: RemapIndexes<T, G, I>,
+{
+    /// Rewrite the indexes held by every item in the bag.
+    fn remap_indexes(&mut self, remap: &Remap<T, G, I>) {
+        for value in self.values_mut() {
+            value.remap_indexes(remap);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Serialization of bags and indexes, enabled by the `serde` feature.
+//!
+//! A bag is serialized with all of its slots, including the generations of vacant slots and the
+//! list of free slots, so an [`Index`] serialized alongside the bag resolves to the same item once
+//! both are deserialized, and a stale one still fails to resolve.
+//!
+//! Every generation is kept as it is, including that of a slot retired at its last generation by
+//! [`FreePolicy::NeverReuse`], which can still be reclaimed.
+//!
+//! ```rust
+//! extern crate serde_json;
+//! use index_bag::{FreePolicy, IndexBag, LookupError};
+//!
+//! let mut bag: IndexBag<u32, u8, u8> = IndexBag::with_free_policy(FreePolicy::NeverReuse);
+//! let mut index = bag.insert(0);
+//! for _ in 0..253 {
+//!     bag.compact(|_, new| index = new);
+//! }
+//! assert_eq!(index.generation(), 253);
+//! bag.remove(index);
+//!
+//! let saved = serde_json::to_string(&bag).unwrap();
+//! let mut bag: IndexBag<u32, u8, u8> = serde_json::from_str(&saved).unwrap();
+//! assert_eq!(bag.generation_of(0), Some(254));
+//! assert!(bag.get_index(0).is_some());
+//! assert_eq!(bag.try_get(index), Err(LookupError::Vacant { generation: 254 }));
+//!
+//! bag.insert(1);
+//! bag.compact(|_, _| {});
+//! assert_eq!(bag.pool_size(), 1);
+//! ```
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use serde::de::Error;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::free_list::FreeList;
+use super::{FreePolicy, Generation, Index, IndexBag, SlotIndex, SlotState, NO_SLOT};
+
+/// The state of a slot, without the position of its item.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum SlotKind {
+    Occupied,
+    Vacant,
+    Reserved,
+    Retired,
+}
+
+impl From<SlotState> for SlotKind {
+    fn from(state: SlotState) -> SlotKind {
+        match state {
+            SlotState::Occupied(_) => SlotKind::Occupied,
+            SlotState::Vacant { .. } => SlotKind::Vacant,
+            SlotState::Reserved => SlotKind::Reserved,
+            SlotState::Retired => SlotKind::Retired,
+        }
+    }
+}
+
+impl<T, G, I> Serialize for Index<T, G, I>
+where
+    G: Generation + Serialize,
+    I: SlotIndex + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.index, self.generation()).serialize(serializer)
+    }
+}
+
+impl<'de, T, G, I> Deserialize<'de> for Index<T, G, I>
+where
+    G: Generation + Deserialize<'de>,
+    I: SlotIndex + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Index<T, G, I>, D::Error> {
+        let (index, generation) = <(I, G)>::deserialize(deserializer)?;
+        let generation = generation.try_into_non_zero()
+            .ok_or_else(|| D::Error::custom("generation out of range"))?;
+        Ok(Index {
+            index,
+            generation,
+            item: PhantomData,
+        })
+    }
+}
+
+impl<T, G, I> Serialize for IndexBag<T, G, I>
+where
+    T: Serialize,
+    G: Generation + Serialize,
+    I: SlotIndex + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("IndexBag", 5)?;
+        state.serialize_field("slots", &Slots(self))?;
+        state.serialize_field("items", &Items(self))?;
+        state.serialize_field("free_indexes", &FreeIndexes(self))?;
+        state.serialize_field("first_generation", &self.first_generation)?;
+        state.serialize_field("free_policy", &self.free_policy())?;
+        state.end()
+    }
+}
+
+/// The slots of a bag as they currently stand, serialized as their kind and generation.
+struct Slots<'a, T: 'a, G: Generation + 'a, I: 'a>(&'a IndexBag<T, G, I>);
+
+impl<'a, T, G, I> Serialize for Slots<'a, T, G, I>
+where
+    G: Generation + Serialize,
+    I: SlotIndex,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bag = self.0;
+        let slots = (0..bag.pool_size()).map(|position| bag.current_slot(position).unwrap());
+        serializer.collect_seq(slots.map(|slot| (SlotKind::from(slot.state), slot.generation)))
+    }
+}
+
+/// The free list of a bag, including the slots emptied by [`IndexBag::reset`], in the order that
+/// rebuilds it.
+struct FreeIndexes<'a, T: 'a, G: Generation + 'a, I: 'a>(&'a IndexBag<T, G, I>);
+
+impl<'a, T, G, I> Serialize for FreeIndexes<'a, T, G, I>
+where
+    G: Generation,
+    I: SlotIndex + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bag = self.0;
+        let cleared = (bag.valid_slots..bag.pool_size())
+            .filter(|&position| bag.current_slot(position).unwrap().state.is_vacant())
+            .map(|position| I::from_usize(position).unwrap());
+        // Emptied slots are reused in order, once the free list is exhausted.
+        let free_indexes = bag.free_indexes.to_vec(&bag.states);
+        match bag.free_policy() {
+            FreePolicy::Lifo => serializer.collect_seq(cleared.rev().chain(free_indexes)),
+            _ => serializer.collect_seq(free_indexes.into_iter().chain(cleared)),
+        }
+    }
+}
+
+/// The items of a bag in order, serialized with the index of their slot.
+struct Items<'a, T: 'a, G: Generation + 'a, I: 'a>(&'a IndexBag<T, G, I>);
+
+impl<'a, T, G, I> Serialize for Items<'a, T, G, I>
+where
+    T: Serialize,
+    G: Generation,
+    I: SlotIndex + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bag = self.0;
+        serializer.collect_seq(bag.keys.iter().zip(&bag.values).map(|(key, value)| (key.index, value)))
+    }
+}
+
+/// The serialized form of a bag, which is checked before the bag is rebuilt from it.
+#[derive(Deserialize)]
+#[serde(rename = "IndexBag")]
+struct RawBag<T, G, I> {
+    slots: Vec<(SlotKind, G)>,
+    items: Vec<(I, T)>,
+    free_indexes: Vec<I>,
+    first_generation: G,
+    #[serde(default)]
+    free_policy: FreePolicy,
+}
+
+impl<'de, T, G, I> Deserialize<'de> for IndexBag<T, G, I>
+where
+    T: Deserialize<'de>,
+    G: Generation + Deserialize<'de>,
+    I: SlotIndex + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<IndexBag<T, G, I>, D::Error> {
+        RawBag::deserialize(deserializer)?
+            .into_bag()
+            .map_err(D::Error::custom)
+    }
+}
+
+impl<T, G: Generation, I: SlotIndex> RawBag<T, G, I> {
+    /// Rebuild the bag, checking that the slots agree with the items and the free list.
+    fn into_bag(self) -> Result<IndexBag<T, G, I>, &'static str> {
+        if let Some(last) = self.slots.len().checked_sub(1) {
+            I::from_usize(last).ok_or("too many slots for the index type")?;
+        }
+        if self.first_generation.try_into_non_zero().is_none() {
+            return Err("generation out of range");
+        }
+
+        let kinds: Vec<SlotKind> = self.slots.iter().map(|&(kind, _)| kind).collect();
+        let mut generations = Vec::with_capacity(self.slots.len());
+        let mut states = Vec::with_capacity(self.slots.len());
+        for (kind, generation) in self.slots {
+            // Occupied slots are given the position of their item below.
+            let state = match kind {
+                SlotKind::Occupied | SlotKind::Vacant => SlotState::Vacant { next_free: NO_SLOT },
+                SlotKind::Reserved => SlotState::Reserved,
+                SlotKind::Retired => SlotState::Retired,
+            };
+            // Only retired slots may have the exhausted generation, which no index can have.
+            let exhausted = kind == SlotKind::Retired && generation == G::exhausted();
+            if !exhausted && generation.try_into_non_zero().is_none() {
+                return Err("generation out of range");
+            }
+            generations.push(generation);
+            states.push(state);
+        }
+
+        let mut values = Vec::with_capacity(self.items.len());
+        let mut keys = Vec::with_capacity(self.items.len());
+        for (index, value) in self.items {
+            let position = index.into_usize();
+            match kinds.get(position) {
+                Some(SlotKind::Occupied) if states[position].is_vacant() => {}
+                Some(SlotKind::Occupied) => return Err("slot holds more than one item"),
+                _ => return Err("item stored in a slot that is not occupied"),
+            }
+            states[position] = SlotState::Occupied(values.len());
+            keys.push(Index::new(index, generations[position]));
+            values.push(value);
+        }
+        if kinds.iter().filter(|&&kind| kind == SlotKind::Occupied).count() != values.len() {
+            return Err("occupied slot holds no item");
+        }
+
+        let mut free = Vec::new();
+        free.resize(states.len(), false);
+        for &index in &self.free_indexes {
+            let position = index.into_usize();
+            match kinds.get(position) {
+                Some(SlotKind::Vacant) if !free[position] => free[position] = true,
+                Some(SlotKind::Vacant) => return Err("free list contains a slot more than once"),
+                _ => return Err("free list contains a slot that is not vacant"),
+            }
+        }
+        if kinds.iter().filter(|&&kind| kind == SlotKind::Vacant).count() != self.free_indexes.len() {
+            return Err("vacant slot missing from the free list");
+        }
+
+        let mut free_indexes = FreeList::new(self.free_policy);
+        for index in self.free_indexes {
+            if !free_indexes.push(index, &mut states) {
+                states[index.into_usize()] = SlotState::Retired;
+            }
+        }
+
+        Ok(IndexBag {
+            generations,
+            states,
+            values,
+            keys,
+            free_indexes,
+            valid_slots: kinds.len(),
+            first_generation: self.first_generation,
+        })
+    }
+}
@@ -0,0 +1,57 @@
+//! Integer types used to index the slots of an [`IndexBag`](crate::IndexBag).
+
+use core::convert::TryFrom;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// The integer type used by an [`Index`](crate::Index) to refer to a slot.
+///
+/// The type is chosen by the third type parameter of the bag, and limits the number of slots that
+/// the bag can hold. Together with a narrow [`Generation`](crate::Generation) this allows for
+/// compact indexes.
+///
+/// ```rust
+/// use std::mem::size_of;
+/// use index_bag::{IndexBag, Index};
+///
+/// let mut bag: IndexBag<&str, u32, u32> = IndexBag::default();
+/// let index = bag.insert("compact");
+/// assert_eq!(size_of::<Index<&str, u32, u32>>(), 8);
+/// assert_eq!(bag.get(index), Some(&"compact"));
+/// ```
+///
+/// Inserting into a bag that has used every slot available to the index type panics.
+///
+/// ```rust,should_panic
+/// use index_bag::IndexBag;
+///
+/// let mut bag: IndexBag<u32, u32, u8> = IndexBag::default();
+/// for i in 0..257 {
+///     bag.insert(i);
+/// }
+/// ```
+pub trait SlotIndex: Copy + Eq + Ord + Hash + Debug {
+    /// Convert the position of a slot to an index, or `None` if it is out of range.
+    fn from_usize(position: usize) -> Option<Self>;
+
+    /// Convert the index to the position of a slot.
+    fn into_usize(self) -> usize;
+}
+
+macro_rules! impl_slot_index {
+    ($($ty:ty),*) => {
+        $(
+            impl SlotIndex for $ty {
+                fn from_usize(position: usize) -> Option<$ty> {
+                    <$ty>::try_from(position).ok()
+                }
+
+                fn into_usize(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_slot_index!(u8, u16, u32, u64, usize);